@@ -0,0 +1,559 @@
+use crate::client::{TikaBuilder, TikaConfig};
+use crate::document::Document;
+use crate::error::{Error, Result};
+use crate::server::{
+    parse_version, ServerHealth, TikaServer, DEFAULT_SHUTDOWN_TIMEOUT, HEALTH_CHECK_TIMEOUT,
+};
+use crate::web::config::{Config, Detector, MimeType, Parser};
+use crate::web::metadata::{Metadata, MetadataValue};
+use crate::web::options::ParseOptions;
+use crate::web::recursive::{ParsedDocument, RecursiveOptions};
+use crate::web::request::{self, DocumentRequest, Outgoing, ParseFlow, Step};
+use crate::web::response;
+use crate::web::structured::{Page, StructuredDocument};
+use crate::web::translate::{Language, Translation, Translator};
+use crate::web::unpack::{ArchiveFormat, UnpackedAll, UnpackedResources};
+use crate::TikaMode;
+use reqwest::{self, Request, Response, Url};
+use std::net::SocketAddr;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+/// The async client to interact with a tika server.
+///
+/// It shares the `TikaConfig` with the blocking `TikaClient` and offers the same endpoints.
+/// A spawned server is launched on a blocking thread, so waiting for the server doesn't block
+/// the runtime.
+///
+/// # Example
+///
+/// Extract the text of a file
+///
+/// ```edition2018
+/// # use rustika::TikaBuilder;
+/// # async fn run() -> rustika::Result<()> {
+/// let client = TikaBuilder::default().start_server_async().await?;
+/// let text = client.extract_text(::std::fs::read("Cargo.toml")?).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncTikaClient {
+    /// configuration of the tika server
+    pub config: TikaConfig,
+    /// endpoint of the tika server
+    server_endpoint: Url,
+    /// the started tika server
+    server: Option<TikaServer>,
+    /// inner client to execute http requests
+    client: reqwest::Client,
+}
+
+impl AsyncTikaClient {
+    /// a client of the shared `server`
+    pub(crate) fn shared(server: TikaServer, client: reqwest::Client) -> Self {
+        AsyncTikaClient {
+            config: server.config().clone(),
+            server_endpoint: server.endpoint().clone(),
+            server: Some(server),
+            client,
+        }
+    }
+
+    /// Starts a local server instance.
+    /// With `SpawnMode::AttachOrSpawn`, a tika server that already answers is attached to instead.
+    pub async fn start_server(&mut self) -> Result<()> {
+        let config = self.config.clone();
+        let server = tokio::task::spawn_blocking(move || TikaServer::start(config))
+            .await
+            .map_err(|e| Error::server(format!("Failed to launch the tika server: {}", e)))??;
+        self.config = server.config().clone();
+        self.server_endpoint = server.endpoint().clone();
+        self.server = Some(server);
+        Ok(())
+    }
+
+    /// The started tika server, which can create more clients
+    pub fn server(&self) -> Option<&TikaServer> {
+        self.server.as_ref()
+    }
+
+    /// restart the server and use a a different local address, if supplied
+    pub async fn restart_server(&mut self, addr: Option<SocketAddr>) -> Result<()> {
+        self.stop_server().await?;
+        if let Some(addr) = addr {
+            self.config.tika_mode = TikaMode::ClientServer(addr);
+            self.server_endpoint = self.config.tika_mode.server_endpoint();
+        }
+        self.start_server().await
+    }
+
+    /// Shuts down the spawned tika server, giving it `DEFAULT_SHUTDOWN_TIMEOUT` to exit
+    /// gracefully
+    pub async fn stop_server(&mut self) -> Result<()> {
+        self.shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await.map(|_| ())
+    }
+
+    /// Shuts down the spawned tika server, also for the other clients sharing it, and returns
+    /// its exit status, `None` if no server was spawned.
+    /// The server is asked to terminate and killed, if it didn't exit within the `timeout`.
+    /// Waiting for the server happens on a blocking thread.
+    pub async fn shutdown(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        let server = match &self.server {
+            Some(server) => server.clone(),
+            None => return Ok(None),
+        };
+        let status = tokio::task::spawn_blocking(move || server.shutdown(timeout))
+            .await
+            .map_err(|e| Error::server(format!("Failed to shutdown the tika server: {}", e)))??;
+        self.server = None;
+        Ok(status)
+    }
+
+    /// The last lines the spawned tika server logged, oldest first.
+    /// All lines are logged with the `log` crate under the `rustika::server` target as well.
+    pub fn server_logs(&self) -> Vec<String> {
+        self.server
+            .as_ref()
+            .map(TikaServer::logs)
+            .unwrap_or_default()
+    }
+
+    /// the endpoint of the tika server
+    pub fn server_endpoint(&self) -> &Url {
+        &self.server_endpoint
+    }
+
+    /// Whether the tika server responds within the `HEALTH_CHECK_TIMEOUT` and, if it was
+    /// spawned by this client, its process is still running
+    pub async fn is_server_live(&self) -> bool {
+        self.health().await.is_live()
+    }
+
+    /// Checks the health of the tika server by requesting its version.
+    /// Servers that don't serve `/version` are probed at `/tika`.
+    pub async fn health(&self) -> ServerHealth {
+        let (reachable, latency, version) = match self.probe("version").await {
+            Ok((latency, body)) => (true, Some(latency), parse_version(&body)),
+            Err(ref e) if e.status_code().is_some() => match self.probe("tika").await {
+                Ok((latency, _)) => (true, Some(latency), None),
+                Err(_) => (false, None, None),
+            },
+            Err(_) => (false, None, None),
+        };
+        ServerHealth {
+            reachable,
+            latency,
+            version,
+            process_alive: self.server.as_ref().and_then(TikaServer::is_running),
+        }
+    }
+
+    /// sends a GET request to `path` with the `HEALTH_CHECK_TIMEOUT`
+    async fn probe(&self, path: &str) -> Result<(Duration, String)> {
+        let start = Instant::now();
+        let resp = self
+            .client
+            .get(self.endpoint_url(path)?)
+            .header(reqwest::header::ACCEPT, "text/plain")
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await?;
+        let body = error_for_status(resp).await?.text().await?;
+        Ok((start.elapsed(), body))
+    }
+
+    /// Joins the configured tika server endpoint with the `path`
+    #[inline]
+    pub fn endpoint_url<T: AsRef<str>>(&self, path: T) -> Result<Url> {
+        Ok(self.server_endpoint.join(path.as_ref())?)
+    }
+
+    #[inline]
+    pub async fn request(&self, request: Request) -> Result<Response> {
+        Ok(self.client.execute(request).await?)
+    }
+
+    /// sends a GET request to the `tika_url` with the `Accept` header set to `application/json`
+    #[inline]
+    pub async fn get_json(&self, path: &str) -> Result<Response> {
+        error_for_status(
+            self.client
+                .get(self.endpoint_url(path)?)
+                .header(reqwest::header::ACCEPT, "application/json")
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Returns all the configured `Detector` of the tika server
+    pub async fn detectors(&self) -> Result<Detector> {
+        let resp = self.get_json(Config::Detectors.path()).await?;
+        Ok(serde_json::from_slice(&read_body(resp).await?)?)
+    }
+
+    /// Returns all the configured `Parser` of the tika server
+    pub async fn parsers(&self) -> Result<Parser> {
+        let resp = self.get_json(Config::Parsers.path()).await?;
+        Ok(serde_json::from_slice(&read_body(resp).await?)?)
+    }
+
+    /// Returns the configured `Parser` with additional information.
+    pub async fn parsers_details(&self) -> Result<Parser> {
+        let resp = self.get_json(Config::ParsersDetails.path()).await?;
+        Ok(serde_json::from_slice(&read_body(resp).await?)?)
+    }
+
+    /// returns all the mime types configured on the server
+    pub async fn mime_types(&self) -> Result<Vec<MimeType>> {
+        let resp = self.get_json(Config::MimeTypes.path()).await?;
+        response::mime_types(read_body(resp).await?)
+    }
+
+    ///  Translates the content of to destination language by auto detecting the source language using the configured translator
+    pub async fn translate_auto<T: Into<Document>, D: Into<Language>>(
+        &self,
+        content: T,
+        dest_lang: D,
+    ) -> Result<Translation> {
+        self.put_translate(
+            content,
+            None,
+            dest_lang.into(),
+            &self.config.tika_translator,
+        )
+        .await
+    }
+
+    ///  Translates the content of source file from src language to destination language using the configured translator
+    pub async fn translate<T: Into<Document>, S: Into<Language>, D: Into<Language>>(
+        &self,
+        content: T,
+        src_lang: S,
+        dest_lang: D,
+    ) -> Result<Translation> {
+        self.put_translate(
+            content,
+            Some(src_lang.into()),
+            dest_lang.into(),
+            &self.config.tika_translator,
+        )
+        .await
+    }
+
+    ///  Translates the content of source file from src language to destination language
+    /// using a specific translator
+    pub async fn translate_with_translator<
+        T: Into<Document>,
+        S: Into<Language>,
+        D: Into<Language>,
+    >(
+        &self,
+        content: T,
+        src_lang: S,
+        dest_lang: D,
+        translator: &Translator,
+    ) -> Result<Translation> {
+        self.put_translate(content, Some(src_lang.into()), dest_lang.into(), translator)
+            .await
+    }
+
+    ///  Translates the content of source file to destination language by auto detecting the source language
+    /// using a specific translator
+    pub async fn translate_with_translator_auto<T: Into<Document>, D: Into<Language>>(
+        &self,
+        content: T,
+        dest_lang: D,
+        translator: &Translator,
+    ) -> Result<Translation> {
+        self.put_translate(content, None, dest_lang.into(), translator)
+            .await
+    }
+
+    async fn put_translate<T: Into<Document>>(
+        &self,
+        content: T,
+        src_lang: Option<Language>,
+        dest_lang: Language,
+        translator: &Translator,
+    ) -> Result<Translation> {
+        self.put_parse(content, request::translate(translator, src_lang, dest_lang))
+            .await
+    }
+
+    /// Detects MIME type of the content.
+    /// The resulting mime type will only include the `identifier` field
+    pub async fn detect_mime<T: Into<Document>>(&self, content: T) -> Result<MimeType> {
+        self.put_parse(content, request::detect_mime()).await
+    }
+
+    /// Detects the language of the content
+    pub async fn detect_language<T: Into<Document>>(&self, content: T) -> Result<Language> {
+        self.put_parse(content, request::detect_language()).await
+    }
+
+    /// Extracts the plain text of the content.
+    pub async fn extract_text<T: Into<Document>>(&self, content: T) -> Result<String> {
+        self.extract_text_with_options(content, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts the plain text of the content using the parse `options`
+    pub async fn extract_text_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<String> {
+        self.put_parse(content, request::text(options)).await
+    }
+
+    /// Extracts the structure of the content, like headings, paragraphs, lists, tables, links
+    /// and pages, from the XHTML the tika server produces.
+    pub async fn extract_structured<T: Into<Document>>(
+        &self,
+        content: T,
+    ) -> Result<StructuredDocument> {
+        self.extract_structured_with_options(content, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts the structure of the content using the parse `options`
+    pub async fn extract_structured_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<StructuredDocument> {
+        self.put_parse(content, request::structured(options)).await
+    }
+
+    /// Extracts the text of each page of paged content, like pdfs.
+    pub async fn extract_pages<T: Into<Document>>(&self, content: T) -> Result<Vec<Page>> {
+        self.extract_pages_with_options(content, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts the text of each page of paged content using the parse `options`
+    pub async fn extract_pages_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<Vec<Page>> {
+        Ok(self
+            .extract_structured_with_options(content, options)
+            .await?
+            .pages)
+    }
+
+    /// Extracts the metadata of the content.
+    pub async fn metadata<T: Into<Document>>(&self, content: T) -> Result<Metadata> {
+        self.metadata_with_options(content, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts the metadata of the content using the parse `options`
+    pub async fn metadata_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<Metadata> {
+        self.put_parse(content, request::metadata(options)).await
+    }
+
+    /// Extracts only the metadata `field` of the content, like `dc:title`.
+    /// Returns `None` if the document has no such field.
+    pub async fn metadata_field<T: Into<Document>, F: AsRef<str>>(
+        &self,
+        content: T,
+        field: F,
+    ) -> Result<Option<MetadataValue>> {
+        self.metadata_field_with_options(content, field, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts only the metadata `field` of the content using the parse `options`
+    pub async fn metadata_field_with_options<T: Into<Document>, F: AsRef<str>>(
+        &self,
+        content: T,
+        field: F,
+        options: &ParseOptions,
+    ) -> Result<Option<MetadataValue>> {
+        self.put_parse(content, request::metadata_field(field.as_ref(), options))
+            .await
+    }
+
+    /// Parses the content and all its embedded documents, like attachments of emails or the
+    /// files of an archive, and returns the container document with its embedded documents.
+    pub async fn recursive_metadata<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &RecursiveOptions,
+    ) -> Result<ParsedDocument> {
+        self.recursive_metadata_with_options(content, options, &ParseOptions::default())
+            .await
+    }
+
+    /// Parses the content and all its embedded documents using the parse `options`
+    pub async fn recursive_metadata_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        recursive: &RecursiveOptions,
+        options: &ParseOptions,
+    ) -> Result<ParsedDocument> {
+        self.put_parse(content, request::recursive_metadata(recursive, options))
+            .await
+    }
+
+    /// Extracts all embedded resources of the content, like the images of a pdf.
+    pub async fn unpack<T: Into<Document>>(&self, content: T) -> Result<UnpackedResources> {
+        self.unpack_with_format(content, ArchiveFormat::default())
+            .await
+    }
+
+    /// Extracts all embedded resources of the content,
+    /// which the tika server sends as archive of the `format`
+    pub async fn unpack_with_format<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+    ) -> Result<UnpackedResources> {
+        self.unpack_with_options(content, format, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts all embedded resources of the content using the parse `options`
+    pub async fn unpack_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedResources> {
+        self.put_unpack(content, "unpack", format, options).await
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata.
+    pub async fn unpack_all<T: Into<Document>>(&self, content: T) -> Result<UnpackedAll> {
+        self.unpack_all_with_format(content, ArchiveFormat::default())
+            .await
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata,
+    /// which the tika server sends as archive of the `format`
+    pub async fn unpack_all_with_format<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+    ) -> Result<UnpackedAll> {
+        self.unpack_all_with_options(content, format, &ParseOptions::default())
+            .await
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata
+    /// using the parse `options`
+    pub async fn unpack_all_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedAll> {
+        Ok(self
+            .put_unpack(content, "unpack/all", format, options)
+            .await?
+            .into())
+    }
+
+    async fn put_unpack<T: Into<Document>>(
+        &self,
+        content: T,
+        path: &str,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedResources> {
+        self.put_parse(content, request::unpack(path, format, options))
+            .await
+    }
+
+    /// Sends the content for the `request` and parses the successful response, like
+    /// `TikaClient` does
+    async fn put_parse<T: Into<Document>, R>(
+        &self,
+        content: T,
+        request: DocumentRequest<R>,
+    ) -> Result<R> {
+        let doc = content.into();
+        let mut flow = ParseFlow::new(request, &doc, self.config.password_provider.as_deref());
+        let mut outgoing = flow.start();
+        loop {
+            let outcome = match self.send_document(&doc, &outgoing).await {
+                Ok(resp) => read_body(resp).await,
+                Err(err) => Err(err),
+            };
+            match flow.next(outcome) {
+                Step::Send(next) => outgoing = next,
+                Step::Done(parsed) => return parsed,
+            }
+        }
+    }
+
+    /// sends the document with the `outgoing` request and turns unsuccessful responses into
+    /// errors
+    async fn send_document(&self, doc: &Document, outgoing: &Outgoing) -> Result<Response> {
+        let body = doc
+            .async_body()
+            .await?
+            .ok_or_else(|| Error::config("The document can't be sent again"))?;
+        let mut req = self
+            .client
+            .put(self.endpoint_url(&outgoing.path)?)
+            .header(reqwest::header::ACCEPT, outgoing.accept);
+        for (name, value) in &outgoing.headers {
+            req = req.header(name.as_str(), value.as_str());
+        }
+        error_for_status(req.body(body).send().await?).await
+    }
+}
+
+/// Converts a response with an unsuccessful HTTP status into an `ErrorKind::Status` error
+/// that contains the body of the response.
+async fn error_for_status(resp: Response) -> Result<Response> {
+    let status = resp.status();
+    if status.is_success() {
+        Ok(resp)
+    } else {
+        let msg = resp.text().await.unwrap_or_default();
+        Err(Error::status(status, msg))
+    }
+}
+
+/// reads the whole body of the response
+async fn read_body(resp: Response) -> Result<Vec<u8>> {
+    Ok(resp.bytes().await?.to_vec())
+}
+
+impl Default for AsyncTikaClient {
+    fn default() -> Self {
+        TikaBuilder::default().build_async()
+    }
+}
+
+impl TikaBuilder {
+    /// creates a new `AsyncTikaClient` and starts the server without blocking the runtime
+    /// if no server file is available, it downloads it first
+    pub async fn start_server_async(self) -> Result<AsyncTikaClient> {
+        let mut client = self.build_async();
+        client.start_server().await?;
+        Ok(client)
+    }
+
+    /// Constructs a new `AsyncTikaClient` based on its configuration
+    pub fn build_async(self) -> AsyncTikaClient {
+        let server_endpoint = self.tika_mode.server_endpoint();
+
+        AsyncTikaClient {
+            client: reqwest::Client::new(),
+            server_endpoint,
+            server: None,
+            config: self,
+        }
+    }
+}
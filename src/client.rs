@@ -1,20 +1,105 @@
+use crate::document::Document;
 use crate::error::{Error, Result};
-use crate::web::config::{Config, Detector, MimeType, MimeTypeInner, Parser};
+use crate::password::PasswordProvider;
+use crate::server::cache::{JarCache, LOGS_DIR};
+use crate::server::config::ServerConfig;
+use crate::server::discovery::{executable_version, Discovery, InstalledServer};
+use crate::server::download::{self, DEFAULT_MAVEN_REPOSITORY};
+use crate::server::jar::{self, JarManifest, ServerArtifact};
+use crate::server::java::JavaRuntime;
+use crate::server::logs::{LogPump, LogRotation, ServerLogs, DEFAULT_LOG_LINES};
+use crate::server::options::ServerOptions;
+use crate::server::{
+    self, parse_version, port, ServerHealth, TikaServer, DEFAULT_SHUTDOWN_TIMEOUT,
+    DEFAULT_STARTUP_TIMEOUT, HEALTH_CHECK_TIMEOUT, LOG_TAIL_LINES,
+};
+use crate::web::config::{Config, Detector, MimeType, Parser};
+use crate::web::metadata::{Metadata, MetadataValue};
+use crate::web::options::ParseOptions;
+use crate::web::recursive::{ParsedDocument, RecursiveOptions};
+use crate::web::request::{self, DocumentRequest, Outgoing, ParseFlow, Step};
+use crate::web::response;
+use crate::web::structured::{Page, StructuredDocument};
 use crate::web::translate::{
     Language, Translation, Translator, TranslatorKey, TranslatorProperties,
 };
+use crate::web::unpack::{ArchiveFormat, UnpackedAll, UnpackedResources};
 use crate::TikaMode;
-use reqwest::{self, Body, IntoUrl, Request, Response, Url};
-use std::io::{BufRead, BufReader};
+use reqwest::blocking::{self, Request, Response};
+use reqwest::Url;
+use std::env;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
-use std::{env, fs};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a spawned tika server is polled until it serves requests
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether a client spawns its own tika server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpawnMode {
+    /// always spawn a new tika server
+    #[default]
+    Always,
+    /// attach to a tika server that already answers at the address and only spawn a new
+    /// server otherwise. An attached server is not shut down by the client.
+    AttachOrSpawn,
+}
 
-#[derive(Debug)]
+/// How a client in `ClientServer` mode gets its tika server
+///
+/// # Example
+///
+/// Share a single tika server between multiple processes, without ever downloading the jar
+///
+/// ```edition2018
+/// # use rustika::TikaBuilder;
+/// use rustika::client::{ServerPolicy, SpawnMode};
+/// # fn run() -> rustika::Result<()> {
+/// let policy = ServerPolicy::default()
+///     .spawn_mode(SpawnMode::AttachOrSpawn)
+///     .download_missing_jar(false);
+/// let client = TikaBuilder::default().server_policy(policy).start_server()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
 pub struct ServerPolicy {
-    addr: Option<SocketAddr>,
-    download_missing_jar: bool,
+    /// the address of the tika server, replaces the address of the `TikaMode`
+    pub addr: Option<SocketAddr>,
+    /// whether a missing server jar is downloaded.
+    /// If not, the client never accesses the network to get the server.
+    pub download_missing_jar: bool,
+    /// whether a running tika server is attached to
+    pub spawn_mode: SpawnMode,
+}
+
+impl ServerPolicy {
+    /// The address of the tika server
+    pub fn addr(mut self, addr: SocketAddr) -> Self {
+        self.addr = Some(addr);
+        self
+    }
+
+    /// Whether a missing server jar is downloaded
+    pub fn download_missing_jar(mut self, download: bool) -> Self {
+        self.download_missing_jar = download;
+        self
+    }
+
+    /// Whether a running tika server is attached to
+    pub fn spawn_mode(mut self, mode: SpawnMode) -> Self {
+        self.spawn_mode = mode;
+        self
+    }
+
+    /// whether a running tika server is attached to
+    pub(crate) fn attaches(&self) -> bool {
+        self.spawn_mode == SpawnMode::AttachOrSpawn
+    }
 }
 
 impl Default for ServerPolicy {
@@ -22,71 +107,51 @@ impl Default for ServerPolicy {
         ServerPolicy {
             addr: None,
             download_missing_jar: true,
+            spawn_mode: SpawnMode::default(),
         }
     }
 }
 
-/// The client to interact with a tika server
-#[derive(Debug)]
+/// The client to interact with a tika server.
+///
+/// Clones of the client share its `TikaServer`, which is shut down once the last of them is
+/// dropped.
+#[derive(Debug, Clone)]
 pub struct TikaClient {
     /// configuration of the tika server
     pub config: TikaConfig,
     /// endpoint of the tika server
     server_endpoint: Url,
-    /// handle to the spawned tika server
-    server_handle: Option<Child>,
+    /// the started tika server
+    server: Option<TikaServer>,
     /// inner client to execute http requests
-    client: reqwest::Client,
+    client: blocking::Client,
 }
 
 impl TikaClient {
-    /// starts a local server instance
-    pub fn start_server(&mut self) -> Result<()> {
-        if let TikaMode::ClientServer(addr) = self.config.tika_mode {
-            let translate_keys = if let Some(props) = &self.config.tika_translator_props {
-                Some(props.property_dir(&self.config.tika_path)?)
-            } else {
-                None
-            };
-
-            let server_file = match self.config.tika_server_file {
-                TikaServerFileLocation::Remote(_) => self.download_server_jar()?,
-                TikaServerFileLocation::File(ref file) => file,
-            };
-
-            let mut handle = server_file.start_server(&addr, translate_keys)?;
-
-            let stderr = handle
-                .stderr
-                .as_mut()
-                .ok_or_else(|| Error::config("Failed to read tika server logs"))?;
-
-            let reader = BufReader::new(stderr);
-
-            // wait until the tika server is launched, which is indicated by a log message
-            for line in reader.lines() {
-                let line = line?;
-                if self.config.server_verbosity == Verbosity::Verbose {
-                    println!("{}", line);
-                }
-                if line.starts_with("INFO  Started Apache Tika server at") {
-                    break;
-                }
-            }
+    /// a client of the shared `server`
+    pub(crate) fn shared(server: TikaServer, client: blocking::Client) -> Self {
+        TikaClient {
+            config: server.config().clone(),
+            server_endpoint: server.endpoint().clone(),
+            server: Some(server),
+            client,
+        }
+    }
 
-            // remove output pipes if verbose logging is configured
-            if self.config.server_verbosity == Verbosity::Verbose {
-                handle.stderr.take();
-                handle.stdout.take();
-            }
+    /// Starts a local server instance.
+    /// With `SpawnMode::AttachOrSpawn`, a tika server that already answers is attached to instead.
+    pub fn start_server(&mut self) -> Result<()> {
+        let server = TikaServer::start(self.config.clone())?;
+        self.config = server.config().clone();
+        self.server_endpoint = server.endpoint().clone();
+        self.server = Some(server);
+        Ok(())
+    }
 
-            self.server_handle = Some(handle);
-            Ok(())
-        } else {
-            Err(Error::config(
-                "Client is configured as `ClientOnly` and can't spawn a server instance,",
-            ))
-        }
+    /// The started tika server, which can create more clients
+    pub fn server(&self) -> Option<&TikaServer> {
+        self.server.as_ref()
     }
 
     /// restart the server and use a a different local address, if supplied
@@ -99,57 +164,31 @@ impl TikaClient {
         self.start_server()
     }
 
-    /// Shuts down the spawned tika server
+    /// Shuts down the spawned tika server, giving it `DEFAULT_SHUTDOWN_TIMEOUT` to exit
+    /// gracefully
     pub fn stop_server(&mut self) -> Result<()> {
-        // kill the spawned server
-        if let Some(child) = &mut self.server_handle {
-            match child.kill() {
-                Err(e) => {
-                    error!(
-                        "Failed to shutdown the running tika server instance on {}: {}",
-                        self.server_endpoint, e
-                    );
-                    Err(Error::server(format!(
-                        "Failed to shutdown the running tika server instance on {}: {}",
-                        self.server_endpoint, e
-                    )))
-                }
-                _ => {
-                    debug!("Shutdown tika server");
-                    Ok(())
-                }
-            }
-        } else {
-            Ok(())
-        }
+        self.shutdown(DEFAULT_SHUTDOWN_TIMEOUT).map(|_| ())
     }
 
-    /// downloads the tika server jar
-    pub(crate) fn download_server_jar(&mut self) -> Result<&TikaServerFile> {
-        debug!("Fetching tika server jar file.");
-        let mut resp = self
-            .client
-            .get(&TikaConfig::remote_server_jar(&self.config.tika_version))
-            .send()?;
-        let server_jar = self.config.tika_path.join("tika-server.jar");
-
-        let mut out = fs::File::create(&server_jar)?;
-        debug!("Downloading tika server jar to {}", server_jar.display());
-        let written = std::io::copy(&mut resp, &mut out)?;
-
-        debug!(
-            "Finished download to {} with size {}.",
-            server_jar.display(),
-            written
-        );
-
-        self.config.tika_server_file =
-            TikaServerFileLocation::File(TikaServerFile::Download(server_jar));
+    /// Shuts down the spawned tika server, also for the other clients sharing it, and returns
+    /// its exit status, `None` if no server was spawned.
+    /// The server is asked to terminate and killed, if it didn't exit within the `timeout`.
+    pub fn shutdown(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        let status = match &self.server {
+            Some(server) => server.shutdown(timeout)?,
+            None => return Ok(None),
+        };
+        self.server = None;
+        Ok(status)
+    }
 
-        match &self.config.tika_server_file {
-            TikaServerFileLocation::File(file) => Ok(file),
-            _ => unreachable!(),
-        }
+    /// The last lines the spawned tika server logged, oldest first.
+    /// All lines are logged with the `log` crate under the `rustika::server` target as well.
+    pub fn server_logs(&self) -> Vec<String> {
+        self.server
+            .as_ref()
+            .map(TikaServer::logs)
+            .unwrap_or_default()
     }
 
     /// the endpoint of the tika server
@@ -157,8 +196,53 @@ impl TikaClient {
         &self.server_endpoint
     }
 
+    /// Whether the tika server responds within the `HEALTH_CHECK_TIMEOUT` and, if it was
+    /// spawned by this client, its process is still running
     pub fn is_server_live(&self) -> bool {
-        unimplemented!()
+        self.health().is_live()
+    }
+
+    /// Checks the health of the tika server by requesting its version.
+    /// Servers that don't serve `/version` are probed at `/tika`.
+    ///
+    /// # Example
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// let client = TikaClient::default();
+    /// let health = client.health();
+    /// if !health.is_live() {
+    ///     println!("tika server is down: {:?}", health);
+    /// }
+    /// ```
+    pub fn health(&self) -> ServerHealth {
+        let (reachable, latency, version) = match self.probe("version") {
+            Ok((latency, body)) => (true, Some(latency), parse_version(&body)),
+            Err(ref e) if e.status_code().is_some() => match self.probe("tika") {
+                Ok((latency, _)) => (true, Some(latency), None),
+                Err(_) => (false, None, None),
+            },
+            Err(_) => (false, None, None),
+        };
+        ServerHealth {
+            reachable,
+            latency,
+            version,
+            process_alive: self.server.as_ref().and_then(TikaServer::is_running),
+        }
+    }
+
+    /// sends a GET request to `path` with the `HEALTH_CHECK_TIMEOUT`
+    fn probe(&self, path: &str) -> Result<(Duration, String)> {
+        let start = Instant::now();
+        let resp = self
+            .client
+            .get(self.endpoint_url(path)?)
+            .header(reqwest::header::ACCEPT, "text/plain")
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()?;
+        let body = error_for_status(resp)?.text()?;
+        Ok((start.elapsed(), body))
     }
 
     /// Joins the configured tika server endpoint with the `path`
@@ -175,11 +259,12 @@ impl TikaClient {
     /// sends a GET request to the `tika_url` with the `Accept` header set to `application/json`
     #[inline]
     pub fn get_json(&self, path: &str) -> Result<Response> {
-        Ok(self
-            .client
-            .get(self.endpoint_url(path)?)
-            .header(reqwest::header::ACCEPT, "application/json")
-            .send()?)
+        error_for_status(
+            self.client
+                .get(self.endpoint_url(path)?)
+                .header(reqwest::header::ACCEPT, "application/json")
+                .send()?,
+        )
     }
 
     /// Returns all the configured `Detector` of the tika server
@@ -211,28 +296,11 @@ impl TikaClient {
 
     /// returns all the mime types configured on the server
     pub fn mime_types(&self) -> Result<Vec<MimeType>> {
-        let resp = self.get_json(Config::MimeTypes.path())?;
-
-        let mimes: std::collections::HashMap<String, serde_json::Value> =
-            serde_json::from_reader(resp)?;
-
-        let mimes: ::std::result::Result<Vec<_>, _> = mimes
-            .into_iter()
-            .map(|(identifier, value)| {
-                serde_json::from_value::<MimeTypeInner>(value).map(|x| MimeType {
-                    identifier,
-                    supertype: x.supertype,
-                    alias: x.alias,
-                    parser: x.parser,
-                })
-            })
-            .collect();
-
-        Ok(mimes?)
+        response::mime_types(read_body(self.get_json(Config::MimeTypes.path())?)?)
     }
 
     ///  Translates the content of to destination language by auto detecting the source language using the configured translator
-    pub fn translate_auto<T: Into<Body>, D: Into<Language>>(
+    pub fn translate_auto<T: Into<Document>, D: Into<Language>>(
         &self,
         content: T,
         dest_lang: D,
@@ -246,7 +314,7 @@ impl TikaClient {
     }
 
     ///  Translates the content of source file from src language to destination language using the configured translator
-    pub fn translate<T: Into<Body>, S: Into<Language>, D: Into<Language>>(
+    pub fn translate<T: Into<Document>, S: Into<Language>, D: Into<Language>>(
         &self,
         content: T,
         src_lang: S,
@@ -261,7 +329,7 @@ impl TikaClient {
     }
     ///  Translates the content of source file from src language to destination language
     /// using a specific translator
-    pub fn translate_with_translator<T: Into<Body>, S: Into<Language>, D: Into<Language>>(
+    pub fn translate_with_translator<T: Into<Document>, S: Into<Language>, D: Into<Language>>(
         &self,
         content: T,
         src_lang: S,
@@ -273,7 +341,11 @@ impl TikaClient {
 
     ///  Translates the content of source file to destination language by auto detecting the source language
     /// using a specific translator
-    pub fn translate_with_translator_auto<T: Into<Body>, S: Into<Language>, D: Into<Language>>(
+    pub fn translate_with_translator_auto<
+        T: Into<Document>,
+        S: Into<Language>,
+        D: Into<Language>,
+    >(
         &self,
         content: T,
         dest_lang: D,
@@ -282,30 +354,14 @@ impl TikaClient {
         self.put_translate(content, None, dest_lang.into(), translator)
     }
 
-    fn put_translate<T: Into<Body>>(
+    fn put_translate<T: Into<Document>>(
         &self,
         content: T,
         src_lang: Option<Language>,
         dest_lang: Language,
         translator: &Translator,
     ) -> Result<Translation> {
-        let mut path = format!("translate/all/{}/", translator.as_str());
-        if let Some(src_lang) = &src_lang {
-            path = format!("{}{}/", path, src_lang.0);
-        }
-        path += &dest_lang.0;
-
-        let mut resp = self
-            .client
-            .put(self.endpoint_url(path)?)
-            .header(reqwest::header::ACCEPT, "text/plain")
-            .body(content.into())
-            .send()?;
-        Ok(Translation {
-            content: resp.text()?,
-            src_lang,
-            dest_lang,
-        })
+        self.put_parse(content, request::translate(translator, src_lang, dest_lang))
     }
 
     /// Detects MIME type of the content.
@@ -324,14 +380,8 @@ impl TikaClient {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn detect_mime<T: Into<Body>>(&self, content: T) -> Result<MimeType> {
-        let mut resp = self
-            .client
-            .put(self.endpoint_url("detect/stream")?)
-            .header(reqwest::header::ACCEPT, "text/plain")
-            .body(content.into())
-            .send()?;
-        Ok(MimeType::new(resp.text()?))
+    pub fn detect_mime<T: Into<Document>>(&self, content: T) -> Result<MimeType> {
+        self.put_parse(content, request::detect_mime())
     }
 
     /// Detects the language of the content
@@ -349,57 +399,540 @@ impl TikaClient {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn detect_language<T: Into<Body>>(&self, content: T) -> Result<Language> {
-        let mut resp = self
+    pub fn detect_language<T: Into<Document>>(&self, content: T) -> Result<Language> {
+        self.put_parse(content, request::detect_language())
+    }
+
+    /// Extracts the plain text of the content.
+    /// If the tika server fails to parse the content, the returned error carries the HTTP status
+    /// of the response, e.g. `422 Unprocessable Entity` for corrupt or encrypted documents.
+    ///
+    /// # Example
+    ///
+    /// Extract the text of a file
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let text = client.extract_text(::std::fs::read("Cargo.toml")?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_text<T: Into<Document>>(&self, content: T) -> Result<String> {
+        self.extract_text_with_options(content, &ParseOptions::default())
+    }
+
+    /// Extracts the plain text of the content using the parse `options`
+    ///
+    /// # Example
+    ///
+    /// Extract the text of a scanned pdf with OCR
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// use rustika::web::options::{ParseOptions, PdfOcrStrategy};
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let options = ParseOptions::default()
+    ///     .ocr_language("eng")
+    ///     .pdf_ocr_strategy(PdfOcrStrategy::OcrOnly);
+    /// let text = client.extract_text_with_options(::std::fs::read("scan.pdf")?, &options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_text_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<String> {
+        self.put_parse(content, request::text(options))
+    }
+
+    /// Extracts the structure of the content, like headings, paragraphs, lists, tables, links
+    /// and pages, from the XHTML the tika server produces.
+    ///
+    /// # Example
+    ///
+    /// Print all headings of a file
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let doc = client.extract_structured(::std::fs::read("test.docx")?)?;
+    /// for (level, heading) in doc.headings() {
+    ///     println!("{} {}", "#".repeat(level as usize), heading);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_structured<T: Into<Document>>(&self, content: T) -> Result<StructuredDocument> {
+        self.extract_structured_with_options(content, &ParseOptions::default())
+    }
+
+    /// Extracts the structure of the content using the parse `options`
+    pub fn extract_structured_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<StructuredDocument> {
+        self.put_parse(content, request::structured(options))
+    }
+
+    /// Extracts the text of each page of paged content, like pdfs, using the
+    /// `<div class="page">` elements of the XHTML the tika server produces.
+    /// Content without pages results in an empty list.
+    ///
+    /// # Example
+    ///
+    /// Print the text of each page of a pdf
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// for page in client.extract_pages(::std::fs::read("test.pdf")?)? {
+    ///     println!("page {} (OCR: {}): {}", page.number, page.ocr_applied, page.text);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_pages<T: Into<Document>>(&self, content: T) -> Result<Vec<Page>> {
+        self.extract_pages_with_options(content, &ParseOptions::default())
+    }
+
+    /// Extracts the text of each page of paged content using the parse `options`
+    pub fn extract_pages_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<Vec<Page>> {
+        Ok(self
+            .extract_structured_with_options(content, options)?
+            .pages)
+    }
+
+    /// Extracts the metadata of the content.
+    ///
+    /// # Example
+    ///
+    /// Get the mime type and size of a file
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let meta = client.metadata(::std::fs::read("Cargo.toml")?)?;
+    /// println!("{:?} {:?}", meta.content_type(), meta.content_length());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn metadata<T: Into<Document>>(&self, content: T) -> Result<Metadata> {
+        self.metadata_with_options(content, &ParseOptions::default())
+    }
+
+    /// Extracts the metadata of the content using the parse `options`
+    pub fn metadata_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &ParseOptions,
+    ) -> Result<Metadata> {
+        self.put_parse(content, request::metadata(options))
+    }
+
+    /// Extracts only the metadata `field` of the content, like `dc:title`.
+    /// Returns `None` if the document has no such field.
+    pub fn metadata_field<T: Into<Document>, F: AsRef<str>>(
+        &self,
+        content: T,
+        field: F,
+    ) -> Result<Option<MetadataValue>> {
+        self.metadata_field_with_options(content, field, &ParseOptions::default())
+    }
+
+    /// Extracts only the metadata `field` of the content using the parse `options`
+    pub fn metadata_field_with_options<T: Into<Document>, F: AsRef<str>>(
+        &self,
+        content: T,
+        field: F,
+        options: &ParseOptions,
+    ) -> Result<Option<MetadataValue>> {
+        self.put_parse(content, request::metadata_field(field.as_ref(), options))
+    }
+
+    /// Parses the content and all its embedded documents, like attachments of emails or the
+    /// files of an archive, and returns the container document with its embedded documents.
+    ///
+    /// # Example
+    ///
+    /// List the paths of all files of an archive
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// use rustika::web::recursive::{RecursiveHandler, RecursiveOptions};
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let options = RecursiveOptions::new(RecursiveHandler::Text).max_embedded_resources(100);
+    /// let doc = client.recursive_metadata(::std::fs::read("test.zip")?, &options)?;
+    /// for embedded in doc.documents() {
+    ///     println!("{:?}", embedded.embedded_resource_path);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn recursive_metadata<T: Into<Document>>(
+        &self,
+        content: T,
+        options: &RecursiveOptions,
+    ) -> Result<ParsedDocument> {
+        self.recursive_metadata_with_options(content, options, &ParseOptions::default())
+    }
+
+    /// Parses the content and all its embedded documents using the parse `options`
+    pub fn recursive_metadata_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        recursive: &RecursiveOptions,
+        options: &ParseOptions,
+    ) -> Result<ParsedDocument> {
+        self.put_parse(content, request::recursive_metadata(recursive, options))
+    }
+
+    /// Extracts all embedded resources of the content, like the images of a pdf.
+    ///
+    /// # Example
+    ///
+    /// Write all images of a pdf into a directory
+    ///
+    /// ```edition2018
+    /// # use rustika::TikaClient;
+    /// use rustika::web::options::ParseOptions;
+    /// use rustika::web::unpack::ArchiveFormat;
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = TikaClient::default();
+    /// let options = ParseOptions::default().pdf_extract_inline_images(true);
+    /// let resources = client.unpack_with_options(
+    ///     ::std::fs::read("test.pdf")?,
+    ///     ArchiveFormat::Zip,
+    ///     &options,
+    /// )?;
+    /// for (name, content) in resources.iter() {
+    ///     println!("{} has {} bytes", name, content.len());
+    /// }
+    /// resources.write_to("images")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn unpack<T: Into<Document>>(&self, content: T) -> Result<UnpackedResources> {
+        self.unpack_with_format(content, ArchiveFormat::default())
+    }
+
+    /// Extracts all embedded resources of the content,
+    /// which the tika server sends as archive of the `format`
+    pub fn unpack_with_format<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+    ) -> Result<UnpackedResources> {
+        self.unpack_with_options(content, format, &ParseOptions::default())
+    }
+
+    /// Extracts all embedded resources of the content using the parse `options`
+    pub fn unpack_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedResources> {
+        self.put_unpack(content, "unpack", format, options)
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata.
+    pub fn unpack_all<T: Into<Document>>(&self, content: T) -> Result<UnpackedAll> {
+        self.unpack_all_with_format(content, ArchiveFormat::default())
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata,
+    /// which the tika server sends as archive of the `format`
+    pub fn unpack_all_with_format<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+    ) -> Result<UnpackedAll> {
+        self.unpack_all_with_options(content, format, &ParseOptions::default())
+    }
+
+    /// Extracts all embedded resources of the content together with its text and metadata
+    /// using the parse `options`
+    pub fn unpack_all_with_options<T: Into<Document>>(
+        &self,
+        content: T,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedAll> {
+        Ok(self
+            .put_unpack(content, "unpack/all", format, options)?
+            .into())
+    }
+
+    fn put_unpack<T: Into<Document>>(
+        &self,
+        content: T,
+        path: &str,
+        format: ArchiveFormat,
+        options: &ParseOptions,
+    ) -> Result<UnpackedResources> {
+        self.put_parse(content, request::unpack(path, format, options))
+    }
+
+    /// Sends the content for the `request` and parses the successful response, see
+    /// `ParseFlow`
+    fn put_parse<T: Into<Document>, R>(
+        &self,
+        content: T,
+        request: DocumentRequest<R>,
+    ) -> Result<R> {
+        let doc = content.into();
+        let mut flow = ParseFlow::new(request, &doc, self.config.password_provider.as_deref());
+        let mut outgoing = flow.start();
+        loop {
+            let outcome = self.send_document(&doc, &outgoing).and_then(read_body);
+            match flow.next(outcome) {
+                Step::Send(next) => outgoing = next,
+                Step::Done(parsed) => return parsed,
+            }
+        }
+    }
+
+    /// sends the document with the `outgoing` request and turns unsuccessful responses into
+    /// errors
+    fn send_document(&self, doc: &Document, outgoing: &Outgoing) -> Result<Response> {
+        let body = doc
+            .body()?
+            .ok_or_else(|| Error::config("The document can't be sent again"))?;
+        let mut req = self
             .client
-            .put(self.endpoint_url("language/stream")?)
-            .header(reqwest::header::ACCEPT, "text/plain")
-            .body(content.into())
-            .send()?;
-        let lang = resp.text()?;
-        if lang.is_empty() {
-            Err(Error::server(
-                "Failed to detect language. Got empty response.",
+            .put(self.endpoint_url(&outgoing.path)?)
+            .header(reqwest::header::ACCEPT, outgoing.accept);
+        for (name, value) in &outgoing.headers {
+            req = req.header(name.as_str(), value.as_str());
+        }
+        error_for_status(req.body(body).send()?)
+    }
+}
+
+/// Converts a response with an unsuccessful HTTP status into an `ErrorKind::Status` error
+/// that contains the body of the response.
+pub(crate) fn error_for_status(resp: Response) -> Result<Response> {
+    let status = resp.status();
+    if status.is_success() {
+        Ok(resp)
+    } else {
+        let msg = resp.text().unwrap_or_default();
+        Err(Error::status(status, msg))
+    }
+}
+
+/// reads the whole body of the response
+pub(crate) fn read_body(mut resp: Response) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    resp.copy_to(&mut body)?;
+    Ok(body)
+}
+
+/// Spawns the tika server of the `config` and waits until it serves requests.
+/// The server jar is downloaded first, if the `config` points to a remote jar.
+///
+/// A server at port 0 is spawned at a free port, which replaces the port in the `TikaMode` of
+/// the `config`. Other ports are checked up front and fail with `ErrorKind::AddrInUse`, if
+/// something is already listening.
+///
+/// If the server exits or doesn't serve requests within the `startup_timeout` of the `config`,
+/// an `ErrorKind::Startup` error with the last lines the server logged is returned.
+pub(crate) fn launch_server(config: &mut TikaConfig, logs: &ServerLogs) -> Result<Child> {
+    let addr = match config.tika_mode {
+        TikaMode::ClientServer(addr) => addr,
+        TikaMode::ClientOnly(_) => {
+            return Err(Error::config(
+                "Client is configured as `ClientOnly` and can't spawn a server instance,",
             ))
-        } else {
-            Ok(lang.into())
+        }
+    };
+    let translate_keys = if let Some(props) = &config.tika_translator_props {
+        Some(props.property_dir(&config.tika_path)?)
+    } else {
+        None
+    };
+
+    let server_file = match config.tika_server_file {
+        TikaServerFileLocation::File(ref file) => file,
+        TikaServerFileLocation::Repository => config.installed_or_download()?,
+        TikaServerFileLocation::Remote(_) => config.download_server_jar()?,
+    }
+    .clone();
+
+    if !server_file.exists() {
+        return Err(Error::config(format!(
+            "The tika server file {} does not exist",
+            server_file.location().display()
+        )));
+    }
+
+    if addr.port() != 0 {
+        port::check_available(addr)?;
+        return spawn_server(config, &server_file, translate_keys.as_deref(), logs);
+    }
+    let mut attempt = 1;
+    loop {
+        let free = port::free_port(addr.ip())?;
+        config.tika_mode = TikaMode::ClientServer(free);
+        match spawn_server(config, &server_file, translate_keys.as_deref(), logs) {
+            Err(ref e) if attempt < port::PORT_ATTEMPTS && port::lost_port(e, free) => {
+                debug!("The port of the tika server was taken, retrying on another port");
+                attempt += 1;
+            }
+            spawned => return spawned,
         }
     }
 }
 
-impl Default for TikaClient {
-    fn default() -> Self {
-        TikaBuilder::default().build()
+/// spawns the `server_file` at the address of the `config` and waits until it is ready
+fn spawn_server(
+    config: &TikaConfig,
+    server_file: &TikaServerFile,
+    translate_keys: Option<&Path>,
+    logs: &ServerLogs,
+) -> Result<Child> {
+    let addr = match config.tika_mode {
+        TikaMode::ClientServer(addr) => addr,
+        TikaMode::ClientOnly(_) => unreachable!("only servers are spawned"),
+    };
+    let mut handle = server_file.start_server(&addr, translate_keys, config)?;
+
+    // keep reading the output of the server, so it doesn't block on full pipes
+    let pump = LogPump::new(
+        logs.clone(),
+        config.server_verbosity == Verbosity::Verbose,
+        &config.tika_path.join(LOGS_DIR),
+        config.server_log_rotation,
+    )?;
+    let mut readers = Vec::new();
+    if let Some(stderr) = handle.stderr.take() {
+        readers.push(pump.drain(stderr));
+    }
+    if let Some(stdout) = handle.stdout.take() {
+        readers.push(pump.drain(stdout));
+    }
+
+    let endpoint = config.tika_mode.server_endpoint();
+    match wait_until_ready(&mut handle, &endpoint, config.startup_timeout) {
+        Ok(()) => {
+            debug!("Tika server is ready at {}", endpoint);
+            Ok(handle)
+        }
+        Err(msg) => {
+            // don't leave a server behind, that is still starting
+            let _ = server::shutdown(&mut handle, Duration::from_secs(0));
+            // give the readers a moment to capture the last lines
+            let start = Instant::now();
+            while readers.iter().any(|reader| !reader.is_finished())
+                && start.elapsed() < READINESS_POLL_INTERVAL
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(Error::startup(msg, logs.tail(LOG_TAIL_LINES)))
+        }
     }
 }
 
-impl Drop for TikaClient {
-    fn drop(&mut self) {
-        // kill the spawned server
-        self.stop_server().unwrap_or_else(|_| {
-            panic!(
-                "Failed to shut down the Tika Server running on {}",
-                self.server_endpoint
-            )
-        });
+/// Polls the `endpoint` until the spawned server answers as tika server, it exits or the
+/// `timeout` passes.
+///
+/// Another service may already listen at the address, so the server is only ready if
+/// `/version` answers with the version of tika and the spawned process is still running.
+fn wait_until_ready(
+    handle: &mut Child,
+    endpoint: &Url,
+    timeout: Duration,
+) -> ::std::result::Result<(), String> {
+    let client = blocking::Client::builder()
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = endpoint.join("version").map_err(|e| e.to_string())?;
+    let exited = |handle: &mut Child| -> ::std::result::Result<(), String> {
+        match handle.try_wait().map_err(|e| e.to_string())? {
+            Some(status) => Err(format!("the server exited with {}", status)),
+            None => Ok(()),
+        }
+    };
+    let start = Instant::now();
+    loop {
+        exited(handle)?;
+        let version = client
+            .get(url.clone())
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .and_then(|resp| resp.text());
+        if let Ok(body) = version {
+            // the response may come from another service, while the server failed to bind
+            exited(handle)?;
+            if server::is_tika_version(&body) {
+                return Ok(());
+            }
+            return Err(format!(
+                "{} doesn't answer as tika server: {}",
+                url,
+                body.trim()
+            ));
+        }
+        if start.elapsed() >= timeout {
+            return Err(format!("the server was not ready after {:?}", timeout));
+        }
+        thread::sleep(READINESS_POLL_INTERVAL);
+    }
+}
+
+/// shuts down the spawned tika server of the `handle`, see `server::shutdown`
+pub(crate) fn stop_server(
+    handle: &mut Option<Child>,
+    endpoint: &Url,
+    timeout: Duration,
+) -> Result<Option<ExitStatus>> {
+    let child = match handle {
+        Some(child) => child,
+        None => return Ok(None),
+    };
+    match server::shutdown(child, timeout) {
+        Ok(status) => {
+            debug!("Shutdown tika server on {}: {}", endpoint, status);
+            handle.take();
+            Ok(Some(status))
+        }
+        Err(e) => Err(Error::server(format!(
+            "Failed to shutdown the running tika server instance on {}: {}",
+            endpoint, e
+        ))),
+    }
+}
+
+impl Default for TikaClient {
+    fn default() -> Self {
+        TikaBuilder::default().build()
     }
 }
 
 /// How a spawned tika server should log to `std::out` and `std::err`
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Default)]
 pub enum Verbosity {
-    /// don't log to current shell, use `Stdio::piped()` instead
+    /// don't print the tika server logs, they are only logged with the `log` crate
+    #[default]
     Silent,
     /// enable logging and print the tika server logs to `std::out`
     Verbose,
 }
 
-impl Default for Verbosity {
-    fn default() -> Self {
-        Verbosity::Silent
-    }
-}
-
 /// All configs of the `TikaClient`
 #[derive(Debug, Clone)]
 pub struct TikaConfig {
@@ -413,25 +946,56 @@ pub struct TikaConfig {
     pub tika_mode: TikaMode,
     /// translator class used to translate docs
     pub tika_translator: Translator,
+    /// the server artifact to download for tika 2.x and later
+    pub server_artifact: ServerArtifact,
+    /// the java executable or java home to launch the tika server jar with, if not the one
+    /// of `JAVA_HOME` or `PATH`
+    pub java_path: Option<PathBuf>,
+    /// the options the tika server is launched with, like its heap
+    pub server_options: ServerOptions,
+    /// the `tika-config.xml` that is generated for the tika server
+    pub server_config: Option<ServerConfig>,
+    /// the base url of the maven repository the server jar is downloaded from
+    pub maven_repository: String,
+    /// how the tika server is spawned
+    pub server_policy: ServerPolicy,
     /// whether the tika server should log to std::out
     pub server_verbosity: Verbosity,
+    /// how long a spawned tika server may take until it serves requests
+    pub startup_timeout: Duration,
+    /// how many of the last lines of a spawned tika server are kept
+    pub server_log_lines: usize,
+    /// how the log files of a spawned tika server are rotated, `None` if no log files are written
+    pub server_log_rotation: Option<LogRotation>,
     /// the api keys for the translation services
     pub tika_translator_props: Option<TranslatorProperties>,
+    /// provides passwords for encrypted documents
+    pub password_provider: Option<Arc<dyn PasswordProvider>>,
 }
 
 impl TikaConfig {
     /// Creates a new builder for the desired `tika_mode`
-    pub fn new(_tika_mode: TikaMode) -> Self {
+    pub fn new(tika_mode: TikaMode) -> Self {
         TikaConfig {
             tika_version: Self::default_version(),
             tika_server_file: TikaServerFileLocation::default(),
             tika_path: env::var("TIKA_PATH")
                 .map(|x| Path::new(&x).into())
                 .unwrap_or_else(|_| env::temp_dir()),
-            tika_mode: TikaMode::default(),
+            tika_mode,
             tika_translator: Self::default_translator(),
+            server_artifact: ServerArtifact::default(),
+            java_path: None,
+            server_options: ServerOptions::default(),
+            server_config: None,
+            maven_repository: Self::default_repository(),
+            server_policy: ServerPolicy::default(),
             server_verbosity: Verbosity::default(),
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+            server_log_lines: DEFAULT_LOG_LINES,
+            server_log_rotation: None,
             tika_translator_props: None,
+            password_provider: None,
         }
     }
 
@@ -441,6 +1005,12 @@ impl TikaConfig {
         env::var("TIKA_VERSION").unwrap_or_else(|_| "1.20".to_string())
     }
 
+    /// The maven repository to download the tika server jar from
+    #[inline]
+    pub(crate) fn default_repository() -> String {
+        env::var("TIKA_MAVEN_REPOSITORY").unwrap_or_else(|_| DEFAULT_MAVEN_REPOSITORY.to_string())
+    }
+
     /// The specific translator class the tika server should use to translate docs
     #[inline]
     pub(crate) fn default_translator() -> Translator {
@@ -449,10 +1019,81 @@ impl TikaConfig {
             .unwrap_or_default()
     }
 
-    /// The endpoint from which the tika server jar can be downloaded
-    #[inline]
-    pub(crate) fn remote_server_jar(version: &str) -> String {
-        format!("http://search.maven.org/remotecontent?filepath=org/apache/tika/tika-server/{}/tika-server-{}.jar", version, version)
+    /// The java runtime the tika server jar is launched with
+    pub fn java_runtime(&self) -> Result<JavaRuntime> {
+        JavaRuntime::resolve(self.java_path.as_deref())
+    }
+
+    /// The tika servers installed on the system
+    pub fn installed_servers(&self) -> Vec<InstalledServer> {
+        Discovery::default().find()
+    }
+
+    /// Uses the installed tika server of the `tika_version`, if there is one, and downloads
+    /// the server jar otherwise
+    pub(crate) fn installed_or_download(&mut self) -> Result<&TikaServerFile> {
+        let installed = self
+            .installed_servers()
+            .into_iter()
+            .find(|server| server.is_version(&self.tika_version));
+        match installed {
+            Some(server) => {
+                debug!(
+                    "Using installed tika server {}",
+                    server.file.location().display()
+                );
+                self.tika_server_file = TikaServerFileLocation::File(server.file);
+                match &self.tika_server_file {
+                    TikaServerFileLocation::File(file) => Ok(file),
+                    _ => unreachable!(),
+                }
+            }
+            None => self.download_server_jar(),
+        }
+    }
+
+    /// Downloads the tika server jar to `tika_path`, unless a verified jar was downloaded before.
+    /// The jar is either downloaded from the `Remote` url of the `tika_server_file` or from the
+    /// `maven_repository`, and verified against the checksum published next to it.
+    ///
+    /// Fails if the jar is missing and the `ServerPolicy` forbids downloads.
+    pub(crate) fn download_server_jar(&mut self) -> Result<&TikaServerFile> {
+        let (url, server_jar) = match &self.tika_server_file {
+            TikaServerFileLocation::Remote(url) => (
+                url.clone(),
+                self.tika_path.join(download::jar_file_name(url)),
+            ),
+            _ => {
+                let cache = self.jar_cache();
+                (
+                    cache.jar_url(&self.tika_version),
+                    cache.jar_path(&self.tika_version),
+                )
+            }
+        };
+        if self.server_policy.download_missing_jar {
+            debug!("Fetching tika server jar file.");
+            download::download_jar(&url, &server_jar)?;
+        } else if !server_jar.is_file() {
+            return Err(Error::config(format!(
+                "The tika server jar {} is missing and downloading it is disabled",
+                server_jar.display()
+            )));
+        }
+
+        self.tika_server_file = TikaServerFileLocation::File(TikaServerFile::Download(server_jar));
+
+        match &self.tika_server_file {
+            TikaServerFileLocation::File(file) => Ok(file),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The `JarCache` over the `tika_path`, that downloads from the `maven_repository`
+    pub fn jar_cache(&self) -> JarCache {
+        JarCache::new(&self.tika_path)
+            .repository(self.maven_repository.clone())
+            .artifact(self.server_artifact)
     }
 }
 
@@ -485,15 +1126,36 @@ impl TikaBuilder {
     /// # Ok(())
     ///  # }
     /// ```
-    pub fn client_only<U: IntoUrl>(server_url: U) -> Result<Self> {
+    pub fn client_only<U: AsRef<str>>(server_url: U) -> Result<Self> {
         Ok(TikaBuilder::new(TikaMode::client_only(server_url)?))
     }
 
-    /// Constructs a new `TikaBuilder` in ClientServer mode, to spawn a new tika server instance at `addr`
+    /// Constructs a new `TikaBuilder` in ClientServer mode, to spawn a new tika server instance at `addr`.
+    /// A server at port 0, like `127.0.0.1:0`, is spawned at a free port.
     pub fn with_server<T: AsRef<str>>(addr: T) -> Result<Self> {
         Ok(TikaBuilder::new(TikaMode::client_server(addr)?))
     }
 
+    /// Spawns the tika server at a free port of its address, instead of a fixed one.
+    /// This way parallel servers, like in tests, don't collide.
+    /// The port the server was spawned at is the one of the `server_endpoint` of the client.
+    ///
+    /// # Example
+    ///
+    /// ```edition2018,no_run
+    /// # fn run() -> rustika::Result<()> {
+    /// let client = rustika::TikaBuilder::default().free_port().start_server()?;
+    /// println!("tika server runs at {}", client.server_endpoint());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn free_port(mut self) -> Self {
+        if let TikaMode::ClientServer(addr) = &mut self.tika_mode {
+            addr.set_port(0);
+        }
+        self
+    }
+
     /// The version of the tika server to download if no `TIKA_SERVER_JAR` is set.
     /// Can be set with `TIKA_VERSION`
     pub fn version<T: Into<String>>(mut self, version: T) -> Self {
@@ -501,6 +1163,73 @@ impl TikaBuilder {
         self
     }
 
+    /// The server artifact to download for tika 2.x and later, either `tika-server-standard`
+    /// or `tika-server-full`. Tika 1.x only ships the `tika-server` artifact.
+    pub fn server_artifact(mut self, artifact: ServerArtifact) -> Self {
+        self.server_artifact = artifact;
+        self
+    }
+
+    /// The base url of the maven repository to download the tika server jar from, like an
+    /// internal Artifactory or Nexus mirror, or a `file://` url of a local repository.
+    /// By default `DEFAULT_MAVEN_REPOSITORY` is used, unless `TIKA_MAVEN_REPOSITORY` is set.
+    ///
+    /// # Example
+    ///
+    /// ```edition2018
+    /// # fn main() -> rustika::Result<()> {
+    /// let client = rustika::TikaBuilder::with_server("127.0.0.1:9998")?
+    ///     .maven_repository("https://artifactory.example.com/maven-remote")
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn maven_repository<T: Into<String>>(mut self, url: T) -> Self {
+        self.maven_repository = url.into();
+        self
+    }
+
+    /// The java executable or java home to launch the tika server jar with.
+    /// By default the java of `JAVA_HOME` is used or, if it is not set, the `java` in `PATH`.
+    pub fn java<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.java_path = Some(path.as_ref().into());
+        self
+    }
+
+    /// The options the tika server is launched with, like its heap or its `tika-config.xml`
+    ///
+    /// # Example
+    ///
+    /// ```edition2018
+    /// use rustika::server::options::ServerOptions;
+    ///
+    /// let builder = rustika::TikaBuilder::default()
+    ///     .server_options(ServerOptions::default().spawn_child(true).max_heap("4g"));
+    /// ```
+    pub fn server_options(mut self, options: ServerOptions) -> Self {
+        self.server_options = options;
+        self
+    }
+
+    /// The `tika-config.xml` to generate for the tika server, like the parsers it uses.
+    /// The config is written to the `tika_path`, see `ServerConfig`.
+    /// Starting the server fails, if the `ServerOptions` point to a `config` as well.
+    pub fn server_config(mut self, config: ServerConfig) -> Self {
+        self.server_config = Some(config);
+        self
+    }
+
+    /// How the tika server is spawned, e.g. whether a running server is attached to or
+    /// whether the server jar may be downloaded.
+    /// The address of the `policy`, if set, replaces the address of the `TikaMode`.
+    pub fn server_policy(mut self, policy: ServerPolicy) -> Self {
+        if let Some(addr) = policy.addr {
+            self.tika_mode = TikaMode::ClientServer(addr);
+        }
+        self.server_policy = policy;
+        self
+    }
+
     /// The path where tika files should be stored.
     /// This will be a tempfile if no `TIKA_PATH` is set
     pub fn path<T: AsRef<Path>>(mut self, path: T) -> Self {
@@ -509,8 +1238,8 @@ impl TikaBuilder {
     }
 
     /// The location of the tika server file.
-    /// If no `TIKA_SERVER_JAR` is or the `tika-rest-server` executable is not on `PATH`,
-    /// this will be a pointer to the remote download link of the tika server jar file.
+    /// If no `TIKA_SERVER_JAR` is set, an installed tika server of the `tika_version` is used
+    /// or, if there is none, the tika server jar is downloaded from the `maven_repository`.
     pub fn server_file(mut self, server_file: TikaServerFileLocation) -> Self {
         self.tika_server_file = server_file;
         self
@@ -531,6 +1260,27 @@ impl TikaBuilder {
         self
     }
 
+    /// How long a spawned tika server may take until it serves requests.
+    /// By default the server has `DEFAULT_STARTUP_TIMEOUT` to start.
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// How many of the last lines of a spawned tika server are kept for `server_logs`.
+    /// By default the last `DEFAULT_LOG_LINES` lines are kept.
+    pub fn server_log_lines(mut self, lines: usize) -> Self {
+        self.server_log_lines = lines;
+        self
+    }
+
+    /// Writes the logs of a spawned tika server into `logs/tika-server.log` within the
+    /// `tika_path`, which is rotated according to the `rotation`
+    pub fn server_log_files(mut self, rotation: LogRotation) -> Self {
+        self.server_log_rotation = Some(rotation);
+        self
+    }
+
     /// sets the directory path to the property files for the translators
     pub fn set_translator_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.tika_translator_props = Some(TranslatorProperties::Dir(path.as_ref().into()));
@@ -548,6 +1298,12 @@ impl TikaBuilder {
         self
     }
 
+    /// The `PasswordProvider` to ask for candidate passwords, if a document is encrypted
+    pub fn password_provider<P: PasswordProvider + 'static>(mut self, provider: P) -> Self {
+        self.password_provider = Some(Arc::new(provider));
+        self
+    }
+
     /// creates a new `TikaClient` and starts the server
     /// if no server file is available, it downloads it first
     pub fn start_server(self) -> Result<TikaClient> {
@@ -561,9 +1317,9 @@ impl TikaBuilder {
         let server_endpoint = self.tika_mode.server_endpoint();
 
         TikaClient {
-            client: reqwest::Client::new(),
+            client: blocking::Client::new(),
             server_endpoint,
-            server_handle: None,
+            server: None,
             config: self,
         }
     }
@@ -575,18 +1331,19 @@ impl TikaBuilder {
 pub enum TikaServerFileLocation {
     /// local jar or executable
     File(TikaServerFile),
-    /// endpoint of the tika server jar
+    /// endpoint of the tika server jar, a `http(s)://` or `file://` url.
+    /// The checksum of the jar is expected next to it, like maven publishes it.
     Remote(String),
+    /// an installed tika server of the configured version or, if there is none, the jar of
+    /// the configured version and artifact in the configured maven repository
+    Repository,
 }
 
 impl Default for TikaServerFileLocation {
     fn default() -> Self {
-        if let Ok(file) = TikaServerFile::from_env() {
-            TikaServerFileLocation::File(file)
-        } else {
-            TikaServerFileLocation::Remote(TikaConfig::remote_server_jar(
-                &TikaConfig::default_version(),
-            ))
+        match TikaServerFile::from_var() {
+            Some(file) => TikaServerFileLocation::File(file),
+            None => TikaServerFileLocation::Repository,
         }
     }
 }
@@ -595,39 +1352,46 @@ impl Default for TikaServerFileLocation {
 /// This can be
 /// an `Os` executable:
 /// (e.g. the homebrew installation of tika includes the system shell script `tika-rest-server` )
-/// or a jar file, which is either downloaded or pointed to by the `TIKA_SERVER_JAR` env variable
+/// or a jar file, which is either installed, downloaded or pointed to by the `TIKA_SERVER_JAR`
+/// env variable
 #[derive(Debug, Clone)]
 pub enum TikaServerFile {
     /// `tika-rest-server`executable directly in `PATH`
     PathExecutable(PathBuf),
     /// env var pointer to a tika server jar
     EnvVarJar(PathBuf),
+    /// a tika server jar installed on the system, like in `/opt/tika`
+    SystemJar(PathBuf),
     /// stores the path to a downloaded server jar either in `TIKA_PATH` or within a temp dir
     Download(PathBuf),
 }
 
 impl TikaServerFile {
-    /// returns a env var pointer, either `TikaServer::Os` or `TikaServer::Var` to a tika server file.
-    /// `TikaServer::Var` trumps any OS variable
+    /// returns a env var pointer, either `TikaServer::Var` to a tika server file or the first
+    /// tika server installed on the system, see `Discovery`.
+    /// `TikaServer::Var` trumps any installed server
     pub fn from_env() -> Result<Self> {
+        if let Some(file) = Self::from_var() {
+            return Ok(file);
+        }
+        Discovery::default()
+            .find()
+            .into_iter()
+            .next()
+            .map(|server| server.file)
+            .ok_or_else(|| Error::config("Could not find a system wide tika server"))
+    }
+
+    /// the jar the `TIKA_SERVER_JAR` env var points to
+    fn from_var() -> Option<Self> {
         match env::var("TIKA_SERVER_JAR").map(PathBuf::from) {
-            Ok(path) => return Ok(TikaServerFile::EnvVarJar(path)),
+            Ok(path) => Some(TikaServerFile::EnvVarJar(path)),
             Err(env::VarError::NotUnicode(var)) => panic!(
                 "TIKA_SERVER_JAR env var found but did not contain valid unicode {:?}",
                 var
             ),
-            _ => (),
-        };
-
-        if cfg!(target_os = "macos") {
-            // find the `tika-rest-server` executable from `Path` installed with homebrew
-            if let Ok(path) = which::which("tika-rest-server") {
-                return Ok(TikaServerFile::PathExecutable(path));
-            }
+            Err(env::VarError::NotPresent) => None,
         }
-        Err(Error::config(
-            "Could not find system wide tika-rest-server executable",
-        ))
     }
 
     /// checks whether the file it points to exists
@@ -640,23 +1404,96 @@ impl TikaServerFile {
         match self {
             TikaServerFile::PathExecutable(path)
             | TikaServerFile::EnvVarJar(path)
+            | TikaServerFile::SystemJar(path)
             | TikaServerFile::Download(path) => path,
         }
     }
 
-    /// starts a new server instance and returns the handle to the spawned process
+    /// The manifest of the server jar, which tells its main class and tika version.
+    /// Fails for executables.
+    pub fn manifest(&self) -> Result<JarManifest> {
+        match self {
+            TikaServerFile::PathExecutable(path) => Err(Error::config(format!(
+                "{} is an executable and not a jar",
+                path.display()
+            ))),
+            TikaServerFile::EnvVarJar(path)
+            | TikaServerFile::SystemJar(path)
+            | TikaServerFile::Download(path) => JarManifest::read(path),
+        }
+    }
+
+    /// The main class to launch the server jar with.
+    /// This is the `Main-Class` of its manifest or, if the jar has none, the main class for
+    /// the tika `version`.
+    pub fn main_class(&self, version: &str) -> String {
+        match self.manifest() {
+            Ok(JarManifest {
+                main_class: Some(main_class),
+                ..
+            }) => main_class,
+            Ok(JarManifest {
+                version: Some(jar_version),
+                ..
+            }) => jar::main_class(&jar_version).to_string(),
+            _ => jar::main_class(version).to_string(),
+        }
+    }
+
+    /// starts a new server instance and returns the handle to the spawned process.
+    /// The `tika_version` of the `config` is the version of the server, if the jar or the
+    /// install dir of the executable doesn't tell. The `server_options` and `server_config`
+    /// of the `config` are rendered for that version.
+    ///
+    /// On unix the server runs in its own process group, so it can be shut down together
+    /// with the processes it starts. It doesn't receive the signals of the terminal, like
+    /// `Ctrl-C`, but is shut down when its client is dropped.
+    ///
+    /// Jars are launched with the `java` runtime, see `JavaRuntime::resolve`, which must be
+    /// recent enough for the tika version.
     pub(crate) fn start_server<P: AsRef<Path>>(
         &self,
         addr: &SocketAddr,
         translate_keys: Option<P>,
+        config: &TikaConfig,
     ) -> Result<Child> {
         debug!("launching tika server from {}", self.location().display());
-        let mut cmd = match self {
-            TikaServerFile::PathExecutable(path) => Command::new(path),
-            TikaServerFile::EnvVarJar(path) | TikaServerFile::Download(path) => {
-                let java_path = which::which("java").expect("Failed to locate java in PATH.");
-                let mut cmd = Command::new(java_path);
-                cmd.arg("-cp");
+        let version_hint = config.tika_version.as_str();
+        let render = |version: &str| {
+            let mut rendered = config
+                .server_options
+                .render(version, config.server_config.as_ref())?;
+            rendered.write_config(&config.tika_path)?;
+            Ok::<_, Error>(rendered)
+        };
+        let (mut cmd, args) = match self {
+            TikaServerFile::PathExecutable(path) => {
+                let version = executable_version(path);
+                let rendered = render(version.as_deref().unwrap_or(version_hint))?;
+                let mut cmd = Command::new(path);
+                // the launcher script starts the JVM, which also picks up these flags
+                if !rendered.jvm_args.is_empty() {
+                    let mut tool_options = env::var("JAVA_TOOL_OPTIONS").unwrap_or_default();
+                    for arg in &rendered.jvm_args {
+                        if !tool_options.is_empty() {
+                            tool_options.push(' ');
+                        }
+                        tool_options += arg;
+                    }
+                    cmd.env("JAVA_TOOL_OPTIONS", tool_options);
+                }
+                (cmd, rendered.args)
+            }
+            TikaServerFile::EnvVarJar(path)
+            | TikaServerFile::SystemJar(path)
+            | TikaServerFile::Download(path) => {
+                let manifest = self.manifest().unwrap_or_default();
+                let version = manifest.version.as_deref().unwrap_or(version_hint);
+                let rendered = render(version)?;
+                let java = JavaRuntime::resolve(config.java_path.as_deref())?;
+                java.check(version)?;
+                let mut cmd = Command::new(java.path());
+                cmd.args(&rendered.jvm_args).arg("-cp");
 
                 let mut clazz_path = String::new();
 
@@ -680,9 +1517,8 @@ impl TikaServerFile {
                     ))
                 })?;
 
-                cmd.arg(clazz_path)
-                    .arg("org.apache.tika.server.TikaServerCli");
-                cmd
+                cmd.arg(clazz_path).arg(self.main_class(version_hint));
+                (cmd, rendered.args)
             }
         };
 
@@ -690,11 +1526,92 @@ impl TikaServerFile {
             .arg(addr.ip().to_string())
             .arg("--port")
             .arg(addr.port().to_string())
+            .args(args)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
 
         debug!("Spawning {:?}", cmd);
 
         Ok(cmd.spawn()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fake_server, serve};
+
+    /// waits until the tika server at `endpoint` of a `fake_server` living for the `lifetime`
+    /// is ready
+    fn wait(
+        endpoint: &Url,
+        lifetime: Duration,
+        timeout: Duration,
+    ) -> ::std::result::Result<(), String> {
+        let mut child = fake_server(lifetime);
+        let ready = wait_until_ready(&mut child, endpoint, timeout);
+        let _ = child.kill();
+        child.wait().unwrap();
+        ready
+    }
+
+    #[test]
+    fn ready_when_tika_answers() {
+        let endpoint = serve(vec![("/version", b"Apache Tika 2.9.1".to_vec())]);
+        let ready = wait(&endpoint, Duration::from_secs(30), Duration::from_secs(5));
+        assert_eq!(ready, Ok(()));
+    }
+
+    #[test]
+    fn not_ready_when_another_service_answers() {
+        let endpoint = serve(vec![("/version", b"It works!".to_vec())]);
+        let ready = wait(&endpoint, Duration::from_secs(30), Duration::from_secs(5));
+        assert!(ready.unwrap_err().contains("doesn't answer as tika server"));
+    }
+
+    #[test]
+    fn not_ready_when_the_server_exited() {
+        let endpoint = serve(vec![("/version", b"Apache Tika 2.9.1".to_vec())]);
+        let mut child = fake_server(Duration::from_secs(0));
+        child.wait().unwrap();
+        let ready = wait_until_ready(&mut child, &endpoint, Duration::from_secs(5));
+        assert!(ready.unwrap_err().contains("exited"));
+    }
+
+    #[test]
+    fn not_ready_when_the_server_exits_while_starting() {
+        // nothing answers at the endpoint
+        let endpoint = Url::parse(&format!("http://{}/", free_local_addr())).unwrap();
+        let ready = wait(
+            &endpoint,
+            Duration::from_millis(500),
+            Duration::from_secs(30),
+        );
+        assert!(ready.unwrap_err().contains("exited"));
+    }
+
+    #[test]
+    fn not_ready_after_the_timeout() {
+        let endpoint = Url::parse(&format!("http://{}/", free_local_addr())).unwrap();
+        let ready = wait(
+            &endpoint,
+            Duration::from_secs(30),
+            Duration::from_millis(500),
+        );
+        assert!(ready.unwrap_err().contains("not ready"));
+    }
+
+    fn free_local_addr() -> SocketAddr {
+        port::free_port("127.0.0.1".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn tika_versions() {
+        assert!(server::is_tika_version("Apache Tika 1.24.1"));
+        assert!(server::is_tika_version("Apache Tika 2.9.1\n"));
+        assert!(!server::is_tika_version(""));
+        assert!(!server::is_tika_version("<html>nginx</html>"));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::error::{Error, Result};
+use reqwest::blocking::Body;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A document that is sent to the tika server.
+///
+/// Besides its content, a document can carry its resource name and declared content type.
+/// Both are sent along with the content (as `Content-Disposition` and `Content-Type` headers)
+/// and help the tika server to detect the type of documents that can't be detected by magic
+/// bytes alone, like `.csv` files or the different `.xlsx` variants.
+///
+/// Documents created from bytes, strings or a path can be sent multiple times,
+/// e.g. to retry parsing with different passwords.
+/// Documents created from a reader, a `File` or a streamed request `Body` can only be sent once.
+///
+/// # Example
+///
+/// Stream a file from disk, the file name is used as resource name
+///
+/// ```edition2018
+/// # use rustika::{Document, TikaClient};
+/// # fn run() -> rustika::Result<()> {
+/// let client = TikaClient::default();
+/// let mime = client.detect_mime(Document::from_path("report.csv")?)?;
+///
+/// let doc = Document::from_bytes(::std::fs::read("export")?)
+///     .with_name("export.xlsx")
+///     .with_content_type("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet");
+/// let text = client.extract_text(doc)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Document {
+    content: Content,
+    /// the resource name of the document, like its file name
+    name: Option<String>,
+    /// the declared content type of the document
+    content_type: Option<String>,
+}
+
+enum Content {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+    /// the reader is taken out of the mutex when the document is sent, which also makes the
+    /// document `Sync`
+    Reader(Mutex<Option<Box<dyn Read + Send>>>),
+    /// a streamed request body, taken out of the mutex like a reader
+    Body(Mutex<Option<Body>>),
+}
+
+impl Document {
+    fn new(content: Content) -> Self {
+        Document {
+            content,
+            name: None,
+            content_type: None,
+        }
+    }
+
+    /// A buffered document
+    pub fn from_bytes<T: Into<Vec<u8>>>(bytes: T) -> Self {
+        Document::new(Content::Bytes(bytes.into()))
+    }
+
+    /// A document that streams the file at `path`.
+    /// The file name is used as resource name.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(Error::config(format!(
+                "Document {} does not exist or is not a file",
+                path.display()
+            )));
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        Ok(Document {
+            name,
+            ..Document::new(Content::Path(path.to_path_buf()))
+        })
+    }
+
+    /// A document that streams the content of the `reader`
+    pub fn from_reader<R: Read + Send + 'static>(reader: R) -> Self {
+        Document::new(Content::Reader(Mutex::new(Some(Box::new(reader)))))
+    }
+
+    /// Sets the resource name of the document, like its file name
+    pub fn with_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the declared content type of the document, like `text/csv`
+    pub fn with_content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// the resource name of the document
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the declared content type of the document
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// the content of the document if it is buffered
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.content {
+            Content::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// whether the document can be sent multiple times
+    pub fn is_resendable(&self) -> bool {
+        match &self.content {
+            Content::Bytes(_) | Content::Path(_) => true,
+            Content::Reader(_) | Content::Body(_) => false,
+        }
+    }
+
+    /// the hex encoded SHA-256 hash of the content, if the document is resendable
+    pub fn sha256(&self) -> Result<Option<String>> {
+        let mut hasher = Sha256::new();
+        match &self.content {
+            Content::Bytes(bytes) => hasher.update(bytes),
+            Content::Path(path) => {
+                let mut file = fs::File::open(path)?;
+                let mut buf = [0; 8192];
+                loop {
+                    match file.read(&mut buf)? {
+                        0 => break,
+                        n => hasher.update(&buf[..n]),
+                    }
+                }
+            }
+            Content::Reader(_) | Content::Body(_) => return Ok(None),
+        }
+        Ok(Some(format!("{:x}", hasher.finalize())))
+    }
+
+    /// the request headers that declare the resource name and content type
+    pub(crate) fn headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(name) = &self.name {
+            headers.push(("Content-Disposition".to_string(), content_disposition(name)));
+        }
+        if let Some(content_type) = &self.content_type {
+            headers.push(("Content-Type".to_string(), content_type.clone()));
+        }
+        headers
+    }
+
+    /// A new request body of the document.
+    /// Streamed documents only have a body the first time, `None` afterwards.
+    pub(crate) fn body(&self) -> Result<Option<Body>> {
+        match &self.content {
+            Content::Bytes(bytes) => Ok(Some(Body::from(bytes.clone()))),
+            Content::Path(path) => Ok(Some(Body::from(fs::File::open(path)?))),
+            Content::Reader(reader) => Ok(take_once(reader).map(Body::new)),
+            Content::Body(body) => Ok(take_once(body)),
+        }
+    }
+
+    /// A new async request body of the document.
+    /// Streamed documents only have a body the first time, `None` afterwards. Their content
+    /// is read on a blocking thread.
+    #[cfg(feature = "async")]
+    pub(crate) async fn async_body(&self) -> Result<Option<reqwest::Body>> {
+        match &self.content {
+            Content::Bytes(bytes) => Ok(Some(reqwest::Body::from(bytes.clone()))),
+            Content::Path(path) => Ok(Some(async_file_body(path).await?)),
+            Content::Reader(reader) => {
+                let mut reader = match take_once(reader) {
+                    Some(reader) => reader,
+                    None => return Ok(None),
+                };
+                let bytes = tokio::task::spawn_blocking(move || {
+                    let mut bytes = Vec::new();
+                    reader.read_to_end(&mut bytes).map(|_| bytes)
+                })
+                .await
+                .map_err(|e| Error::server(format!("Failed to read document: {}", e)))??;
+                Ok(Some(reqwest::Body::from(bytes)))
+            }
+            Content::Body(body) => match take_once(body) {
+                Some(_) => Err(Error::config(
+                    "Streamed request bodies can only be sent by the blocking client",
+                )),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+/// takes the streamed content out of the document, `None` if it was sent before
+fn take_once<T>(content: &Mutex<Option<T>>) -> Option<T> {
+    content.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// streams the file at `path`
+#[cfg(feature = "async")]
+async fn async_file_body(path: &Path) -> Result<reqwest::Body> {
+    let file = tokio::fs::File::open(path).await?;
+    Ok(reqwest::Body::wrap_stream(
+        tokio_util::io::ReaderStream::new(file),
+    ))
+}
+
+/// The value of a `Content-Disposition` header of an attachment with the file `name`.
+/// Names that are not plain ascii are additionally encoded as `filename*` (RFC 5987).
+fn content_disposition(name: &str) -> String {
+    let ascii: String = name
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+    if ascii == name {
+        return format!("attachment; filename=\"{}\"", ascii);
+    }
+    let encoded: String =
+        name.bytes()
+            .map(|b| match b {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (b as char).to_string(),
+                b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|'
+                | b'~' => (b as char).to_string(),
+                b => format!("%{:02X}", b),
+            })
+            .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii, encoded
+    )
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Document");
+        match &self.content {
+            Content::Bytes(bytes) => s.field("len", &bytes.len()),
+            Content::Path(path) => s.field("path", path),
+            Content::Reader(_) | Content::Body(_) => s.field("stream", &true),
+        };
+        s.field("name", &self.name)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+impl From<Vec<u8>> for Document {
+    fn from(bytes: Vec<u8>) -> Self {
+        Document::from_bytes(bytes)
+    }
+}
+
+impl From<&'static [u8]> for Document {
+    fn from(bytes: &'static [u8]) -> Self {
+        Document::from_bytes(bytes)
+    }
+}
+
+impl From<String> for Document {
+    fn from(s: String) -> Self {
+        Document::from_bytes(s)
+    }
+}
+
+impl From<&'static str> for Document {
+    fn from(s: &'static str) -> Self {
+        Document::from_bytes(s)
+    }
+}
+
+impl From<fs::File> for Document {
+    fn from(file: fs::File) -> Self {
+        Document::from_reader(file)
+    }
+}
+
+/// Buffered bodies stay resendable, streamed bodies are sent once like a reader without
+/// a name.
+impl From<Body> for Document {
+    fn from(body: Body) -> Self {
+        match body.as_bytes() {
+            Some(bytes) => Document::from_bytes(bytes),
+            None => Document::new(Content::Body(Mutex::new(Some(body)))),
+        }
+    }
+}
+
+impl From<io::Cursor<Vec<u8>>> for Document {
+    fn from(cursor: io::Cursor<Vec<u8>>) -> Self {
+        Document::from_bytes(cursor.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_bodies_are_documents() {
+        let buffered = Document::from(Body::from("text"));
+        assert!(buffered.is_resendable());
+        assert_eq!(buffered.bytes(), Some(&b"text"[..]));
+
+        let streamed = Document::from(Body::new(io::Cursor::new(b"text".to_vec())));
+        assert!(!streamed.is_resendable());
+        assert!(streamed.body().unwrap().is_some());
+        assert!(streamed.body().unwrap().is_none());
+    }
+}
@@ -1,4 +1,9 @@
+// the `Fail` derive of `failure` expands to impls within an anonymous const
+#![allow(non_local_definitions)]
+
+use crate::server::download::ChecksumAlgorithm;
 use failure::{Backtrace, Context, Fail};
+use reqwest::StatusCode;
 use std::{fmt, result};
 
 /// A type alias for handling errors throughout rustika.
@@ -22,6 +27,58 @@ impl Error {
             msg: msg.as_ref().to_string(),
         })
     }
+    pub(crate) fn encrypted<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Encrypted {
+            msg: msg.as_ref().to_string(),
+        })
+    }
+    pub(crate) fn startup<T: AsRef<str>>(msg: T, stderr: Vec<String>) -> Error {
+        Error::from(ErrorKind::Startup {
+            msg: msg.as_ref().to_string(),
+            stderr: stderr.join("\n"),
+        })
+    }
+    pub(crate) fn checksum<T: Into<String>>(
+        url: T,
+        algorithm: ChecksumAlgorithm,
+        expected: String,
+        actual: String,
+    ) -> Error {
+        Error::from(ErrorKind::Checksum {
+            url: url.into(),
+            algorithm,
+            expected,
+            actual,
+        })
+    }
+    pub(crate) fn status<T: Into<String>>(status: StatusCode, msg: T) -> Error {
+        Error::from(ErrorKind::Status {
+            status,
+            msg: msg.into(),
+        })
+    }
+
+    /// Return the kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        self.ctx.get_context()
+    }
+
+    /// Whether the document could not be parsed, because it is encrypted.
+    /// Without a `PasswordProvider`, this is only detected if the tika server names the
+    /// exception in its responses, which it does if it returns stack traces.
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Encrypted { .. })
+    }
+
+    /// The HTTP status the tika server responded with, if this error was caused by an
+    /// unsuccessful response.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self.kind() {
+            ErrorKind::Status { status, .. } => Some(*status),
+            ErrorKind::ReqWest { reqwest } => reqwest.status(),
+            _ => None,
+        }
+    }
 }
 
 impl Fail for Error {
@@ -48,6 +105,74 @@ pub enum ErrorKind {
     #[fail(display = "Tika Server Error: {}", msg)]
     Server { msg: String },
 
+    /// the tika server responded with an unsuccessful status code,
+    /// e.g. `422 Unprocessable Entity` if the document could not be parsed
+    #[fail(display = "Tika Server responded with {}: {}", status, msg)]
+    Status {
+        /// the HTTP status of the response
+        status: StatusCode,
+        /// the body of the response
+        msg: String,
+    },
+
+    /// a spawned tika server exited or was not ready within the startup timeout
+    #[fail(display = "Failed to start the tika server: {}\n{}", msg, stderr)]
+    Startup {
+        /// why the server failed to start
+        msg: String,
+        /// the last lines the server logged
+        stderr: String,
+    },
+
+    /// the address the tika server should be spawned at is already in use
+    #[fail(display = "The address {} is already in use: {}", addr, msg)]
+    AddrInUse {
+        /// the address of the tika server
+        addr: std::net::SocketAddr,
+        /// what is running at the address
+        msg: String,
+    },
+
+    /// a downloaded tika server jar doesn't match its published checksum
+    #[fail(
+        display = "{} checksum mismatch for {}: expected {}, got {}",
+        algorithm, url, expected, actual
+    )]
+    Checksum {
+        /// the url of the downloaded jar
+        url: String,
+        /// the hash algorithm of the checksum
+        algorithm: ChecksumAlgorithm,
+        /// the published checksum
+        expected: String,
+        /// the checksum of the downloaded jar
+        actual: String,
+    },
+
+    /// no java runtime to launch the tika server jar with was found
+    #[fail(display = "No java runtime found: {}", msg)]
+    JavaNotFound { msg: String },
+
+    /// the java runtime is too old for the tika server
+    #[fail(
+        display = "{} is java {}, but tika {} requires java {} or later",
+        java, version, tika_version, required
+    )]
+    JavaVersion {
+        /// the java executable
+        java: String,
+        /// the version of the java runtime
+        version: String,
+        /// the version of the tika server
+        tika_version: String,
+        /// the minimum java feature release the tika server requires
+        required: u32,
+    },
+
+    /// the document is encrypted and none of the available passwords could decrypt it
+    #[fail(display = "Encrypted document: {}", msg)]
+    Encrypted { msg: String },
+
     /// a config error
     #[fail(display = "{}", msg)]
     Config {
@@ -65,8 +190,16 @@ pub enum ErrorKind {
     #[fail(display = "invalid serde: {}", error)]
     Serde { error: serde_json::Error },
 
+    /// if a zip archive returned by the tika server could not be read
+    #[fail(display = "Failed to read zip archive: {}", zip)]
+    Zip { zip: zip::result::ZipError },
+
+    /// if the XHTML returned by the tika server could not be parsed
+    #[fail(display = "Failed to parse xml: {}", xml)]
+    Xml { xml: quick_xml::Error },
+
     #[fail(display = "Failed to parse url: {}", url)]
-    Url { url: reqwest::UrlError },
+    Url { url: url::ParseError },
     // TODO unify to single parse error
     #[fail(display = "Failed to parse address: {}", addr)]
     Addr { addr: std::net::AddrParseError },
@@ -96,8 +229,20 @@ impl From<reqwest::Error> for Error {
     }
 }
 
-impl From<reqwest::UrlError> for Error {
-    fn from(url: reqwest::UrlError) -> Error {
+impl From<zip::result::ZipError> for Error {
+    fn from(zip: zip::result::ZipError) -> Error {
+        ErrorKind::Zip { zip }.into()
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(xml: quick_xml::Error) -> Error {
+        ErrorKind::Xml { xml }.into()
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(url: url::ParseError) -> Error {
         ErrorKind::Url { url }.into()
     }
 }
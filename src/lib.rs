@@ -3,15 +3,25 @@ extern crate serde;
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "async")]
+pub mod async_client;
 pub mod client;
+pub mod document;
 mod error;
+pub mod password;
 pub mod server;
+#[cfg(test)]
+mod test_util;
 pub mod web;
 
+#[cfg(feature = "async")]
+pub use crate::async_client::AsyncTikaClient;
 pub use crate::client::{TikaBuilder, TikaClient};
-pub use crate::error::Result;
+pub use crate::document::Document;
+pub use crate::error::{Error, ErrorKind, Result};
+pub use crate::server::TikaServer;
 
-use reqwest::{IntoUrl, Url};
+use reqwest::Url;
 use std::net;
 
 /// Indicates whether a tika server instance should spawned or is already running
@@ -40,8 +50,8 @@ impl TikaMode {
     }
 
     /// Creates a `TikaMode::ClientOnly` with the desired `server_url` as tika server endpoint
-    pub fn client_only<U: IntoUrl>(server_url: U) -> Result<Self> {
-        Ok(TikaMode::ClientOnly(server_url.into_url()?))
+    pub fn client_only<U: AsRef<str>>(server_url: U) -> Result<Self> {
+        Ok(TikaMode::ClientOnly(Url::parse(server_url.as_ref())?))
     }
 }
 
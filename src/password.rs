@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// The name of the java exception the tika server reports for encrypted documents
+pub(crate) const ENCRYPTED_EXCEPTION: &str = "EncryptedDocumentException";
+
+/// Information about an encrypted document, that is passed to a `PasswordProvider`
+#[derive(Debug, Clone)]
+pub struct DocumentInfo<'a> {
+    /// the resource name of the document, if known
+    pub name: Option<&'a str>,
+    /// the hex encoded SHA-256 hash of the content of the document
+    pub sha256: String,
+}
+
+/// Provides candidate passwords for encrypted documents.
+///
+/// If the tika server reports that a document is encrypted, the `TikaClient` asks its
+/// `PasswordProvider` for candidate passwords and retries parsing with each of them,
+/// sent in the `Password` header.
+///
+/// # Example
+///
+/// Try the passwords of a keyring, that stores them by the hash of the document
+///
+/// ```edition2018
+/// # use std::collections::HashMap;
+/// # use rustika::TikaBuilder;
+/// # fn run() -> rustika::Result<()> {
+/// let mut keyring: HashMap<String, Vec<String>> = HashMap::new();
+/// let client = TikaBuilder::client_only("http://localhost:9998")?
+///     .password_provider(move |doc: &rustika::password::DocumentInfo| {
+///         keyring.get(&doc.sha256).cloned().unwrap_or_default()
+///     })
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+pub trait PasswordProvider: Send + Sync {
+    /// all candidate passwords for the `document`, in the order they should be tried
+    fn passwords(&self, document: &DocumentInfo) -> Vec<String>;
+}
+
+impl<F> PasswordProvider for F
+where
+    F: Fn(&DocumentInfo) -> Vec<String> + Send + Sync,
+{
+    fn passwords(&self, document: &DocumentInfo) -> Vec<String> {
+        self(document)
+    }
+}
+
+/// Tries the same passwords for every document
+impl PasswordProvider for Vec<String> {
+    fn passwords(&self, _document: &DocumentInfo) -> Vec<String> {
+        self.clone()
+    }
+}
+
+impl fmt::Debug for dyn PasswordProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PasswordProvider")
+    }
+}
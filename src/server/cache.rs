@@ -0,0 +1,272 @@
+use crate::error::Result;
+use crate::server::download::{
+    self, checksum_file, file_digest, ChecksumAlgorithm, DEFAULT_MAVEN_REPOSITORY,
+};
+use crate::server::jar::{JarManifest, ServerArtifact};
+use crate::server::logs::LOG_FILE;
+use fs2::FileExt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The dir within the `tika_path` the property files of the translator keys are written to
+pub const LANGUAGE_KEYS_DIR: &str = "language-keys";
+
+/// The dir within the `tika_path` the generated `tika-config.xml` files are written to
+pub const SERVER_CONFIG_DIR: &str = "tika-config";
+
+/// The dir within the `tika_path` the log files of a spawned tika server are written to
+pub const LOGS_DIR: &str = "logs";
+
+/// Whether a cached jar still matches the checksum it was verified against when downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// the jar matches its checksum
+    Verified(ChecksumAlgorithm),
+    /// the jar changed since it was downloaded
+    Mismatch(ChecksumAlgorithm),
+    /// no checksum is kept for the jar, e.g. because it was not downloaded by rustika
+    Unknown,
+}
+
+/// A tika server jar within the `JarCache`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedJar {
+    /// the location of the jar
+    pub path: PathBuf,
+    /// the tika version of the jar, like `2.9.2`
+    pub version: Option<String>,
+    /// the size of the jar in bytes
+    pub size: u64,
+    /// whether the jar still matches its checksum
+    pub checksum: ChecksumStatus,
+}
+
+/// The tika server jars and other files rustika keeps in the `tika_path`
+///
+/// # Example
+///
+/// ```edition2018,no_run
+/// # fn run() -> rustika::Result<()> {
+/// let cache = rustika::TikaBuilder::default().jar_cache();
+/// cache.prefetch("2.9.2")?;
+/// for jar in cache.jars()? {
+///     println!("{} {:?} {:?}", jar.path.display(), jar.version, jar.checksum);
+/// }
+/// cache.prune(&["2.9.2"])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JarCache {
+    path: PathBuf,
+    repository: String,
+    artifact: ServerArtifact,
+}
+
+impl JarCache {
+    /// A cache in `path`, that downloads the `ServerArtifact::Standard` jars from the
+    /// `DEFAULT_MAVEN_REPOSITORY`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        JarCache {
+            path: path.as_ref().into(),
+            repository: DEFAULT_MAVEN_REPOSITORY.to_string(),
+            artifact: ServerArtifact::default(),
+        }
+    }
+
+    /// The base url of the maven repository to download jars from
+    pub fn repository<T: Into<String>>(mut self, url: T) -> Self {
+        self.repository = url.into();
+        self
+    }
+
+    /// The server artifact to download for tika 2.x and later
+    pub fn artifact(mut self, artifact: ServerArtifact) -> Self {
+        self.artifact = artifact;
+        self
+    }
+
+    /// the dir of the cache
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the location of the jar of the tika `version`, whether it is cached or not
+    pub fn jar_path(&self, version: &str) -> PathBuf {
+        self.path.join(format!(
+            "{}-{}.jar",
+            self.artifact.artifact_id(version),
+            version
+        ))
+    }
+
+    /// the url of the jar of the tika `version` within the maven repository
+    pub fn jar_url(&self, version: &str) -> String {
+        let artifact = self.artifact.artifact_id(version);
+        format!(
+            "{}/org/apache/tika/{}/{}/{}-{}.jar",
+            self.repository.trim_end_matches('/'),
+            artifact,
+            version,
+            artifact,
+            version
+        )
+    }
+
+    /// the dir the property files of the translator keys are written to
+    pub fn language_keys_dir(&self) -> PathBuf {
+        self.path.join(LANGUAGE_KEYS_DIR)
+    }
+
+    /// the dir the log files of a spawned tika server are written to
+    pub fn logs_dir(&self) -> PathBuf {
+        self.path.join(LOGS_DIR)
+    }
+
+    /// the current log file of a spawned tika server
+    pub fn log_file(&self) -> PathBuf {
+        self.logs_dir().join(LOG_FILE)
+    }
+
+    /// The cached tika server jars, sorted by their file name.
+    /// Jars are recognized by their artifact name, like `tika-server-standard-2.9.2.jar`, or
+    /// by the checksum kept next to them after they were downloaded.
+    pub fn jars(&self) -> Result<Vec<CachedJar>> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut jars = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "jar") {
+                continue;
+            }
+            let checksum = checksum_status(&path)?;
+            let named_version = jar_version(&path);
+            if named_version.is_none() && checksum == ChecksumStatus::Unknown {
+                continue;
+            }
+            let version =
+                named_version.or_else(|| JarManifest::read(&path).ok().and_then(|m| m.version));
+            jars.push(CachedJar {
+                size: path.metadata()?.len(),
+                path,
+                version,
+                checksum,
+            });
+        }
+        jars.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(jars)
+    }
+
+    /// Downloads the jar of the tika `version`, unless it is cached and verified already
+    pub fn prefetch(&self, version: &str) -> Result<CachedJar> {
+        let path = self.jar_path(version);
+        download::download_jar(&self.jar_url(version), &path)?;
+        Ok(CachedJar {
+            size: path.metadata()?.len(),
+            checksum: checksum_status(&path)?,
+            version: Some(version.to_string()),
+            path,
+        })
+    }
+
+    /// Removes the cached jars whose version is not in `keep`, together with the files kept
+    /// next to them, and leftovers of aborted downloads.
+    /// Downloads in progress, also of other processes, are left alone.
+    ///
+    /// Returns the removed files.
+    pub fn prune<S: AsRef<str>>(&self, keep: &[S]) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        for jar in self.jars()? {
+            let used = jar
+                .version
+                .as_ref()
+                .is_some_and(|version| keep.iter().any(|keep| keep.as_ref() == version));
+            if used {
+                continue;
+            }
+            fs::remove_file(&jar.path)?;
+            removed.push(jar.path.clone());
+            for algorithm in ChecksumAlgorithm::all() {
+                let checksum = checksum_file(&jar.path, *algorithm);
+                if checksum.is_file() {
+                    fs::remove_file(&checksum)?;
+                    removed.push(checksum);
+                }
+            }
+        }
+        removed.extend(self.remove_leftovers()?);
+        Ok(removed)
+    }
+
+    /// removes the `.lock` and `.part-<pid>` files of downloads that are not in progress
+    fn remove_leftovers(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        if !self.path.is_dir() {
+            return Ok(removed);
+        }
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let jar = match name.strip_suffix(".lock") {
+                Some(jar) if jar.ends_with(".jar") => path.with_file_name(jar),
+                _ => continue,
+            };
+            let lock = download::open_lock(&jar)?;
+            if lock.try_lock_exclusive().is_err() {
+                continue;
+            }
+            let part_prefix = format!(
+                "{}.part-",
+                jar.file_name().unwrap_or_default().to_string_lossy()
+            );
+            for part in fs::read_dir(&self.path)? {
+                let part = part?.path();
+                let is_part = part
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&part_prefix));
+                if is_part {
+                    fs::remove_file(&part)?;
+                    removed.push(part);
+                }
+            }
+            if !jar.exists() {
+                fs::remove_file(&path)?;
+                removed.push(path);
+            }
+            let _ = lock.unlock();
+        }
+        Ok(removed)
+    }
+}
+
+/// the version of a jar named like a tika server artifact, like `tika-server-standard-2.9.2.jar`
+pub(crate) fn jar_version(jar: &Path) -> Option<String> {
+    let name = jar.file_stem()?.to_str()?;
+    ["tika-server-standard-", "tika-server-full-", "tika-server-"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// checks the `jar` against the checksum kept next to it
+pub(crate) fn checksum_status(jar: &Path) -> Result<ChecksumStatus> {
+    for algorithm in ChecksumAlgorithm::all() {
+        let expected = match fs::read_to_string(checksum_file(jar, *algorithm)) {
+            Ok(expected) => expected,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let actual = file_digest(jar, *algorithm)?;
+        return Ok(if actual.eq_ignore_ascii_case(expected.trim()) {
+            ChecksumStatus::Verified(*algorithm)
+        } else {
+            ChecksumStatus::Mismatch(*algorithm)
+        });
+    }
+    Ok(ChecksumStatus::Unknown)
+}
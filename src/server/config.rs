@@ -0,0 +1,567 @@
+use crate::error::{Error, Result};
+use crate::server::cache::SERVER_CONFIG_DIR;
+use crate::server::download::with_suffix;
+use crate::web::config::{Detector, Parser};
+use quick_xml::escape::escape;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The parser that includes all parsers on the classpath of the server
+pub const DEFAULT_PARSER: &str = "org.apache.tika.parser.DefaultParser";
+
+/// The parser of PDF documents, with params like `sortByPosition` or `ocrStrategy`
+pub const PDF_PARSER: &str = "org.apache.tika.parser.pdf.PDFParser";
+
+/// The value of a `Param`
+#[derive(Debug, Clone, PartialEq)]
+#[allow(missing_docs)]
+pub enum ParamValue {
+    Bool(bool),
+    Int(i32),
+    Long(i64),
+    Double(f64),
+    String(String),
+    List(Vec<String>),
+}
+
+impl ParamValue {
+    /// the name of the type within the `tika-config.xml`
+    fn type_name(&self) -> &'static str {
+        match self {
+            ParamValue::Bool(_) => "bool",
+            ParamValue::Int(_) => "int",
+            ParamValue::Long(_) => "long",
+            ParamValue::Double(_) => "double",
+            ParamValue::String(_) => "string",
+            ParamValue::List(_) => "list",
+        }
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<i32> for ParamValue {
+    fn from(value: i32) -> Self {
+        ParamValue::Int(value)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self {
+        ParamValue::Long(value)
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self {
+        ParamValue::Double(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::String(value.to_string())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::String(value)
+    }
+}
+
+impl From<Vec<String>> for ParamValue {
+    fn from(value: Vec<String>) -> Self {
+        ParamValue::List(value)
+    }
+}
+
+/// A named param of a parser or a metadata filter, like `sortByPosition` of the `PDFParser`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// the name of the param
+    pub name: String,
+    /// the typed value of the param
+    pub value: ParamValue,
+}
+
+impl Param {
+    /// A new param `name` with the `value`
+    pub fn new<T: Into<String>, V: Into<ParamValue>>(name: T, value: V) -> Self {
+        Param {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// A metadata filter, that modifies the metadata of parsed documents
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataFilter {
+    /// the jvm class of the filter, like `org.apache.tika.metadata.filter.IncludeFieldMetadataFilter`
+    pub class: String,
+    /// the params of the filter
+    pub params: Vec<Param>,
+}
+
+impl MetadataFilter {
+    /// A new filter of the jvm `class`
+    pub fn new<T: Into<String>>(class: T) -> Self {
+        MetadataFilter {
+            class: class.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a param to the filter
+    pub fn param(mut self, param: Param) -> Self {
+        self.params.push(param);
+        self
+    }
+}
+
+/// Generates the `tika-config.xml` of a spawned tika server
+///
+/// The parsers, detectors and encoding detectors are referred to by their jvm class names.
+/// Detectors and encoding detectors replace the default ones in the order they are added.
+///
+/// # Example
+///
+/// Sort the text of PDFs by its position and don't OCR them
+///
+/// ```edition2018,no_run
+/// # fn run() -> rustika::Result<()> {
+/// use rustika::server::config::{Param, ServerConfig, PDF_PARSER};
+///
+/// let config = ServerConfig::default()
+///     .exclude_parser("org.apache.tika.parser.executable.ExecutableParser")
+///     .parser_param(PDF_PARSER, Param::new("sortByPosition", true))
+///     .parser_param(PDF_PARSER, Param::new("ocrStrategy", "no_ocr"));
+///
+/// // check the class names against a server with the default config
+/// let client = rustika::TikaBuilder::default().start_server()?;
+/// config.check(&client.parsers()?, &client.detectors()?)?;
+///
+/// let client = rustika::TikaBuilder::default()
+///     .server_config(config)
+///     .start_server()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerConfig {
+    included_parsers: Vec<String>,
+    excluded_parsers: Vec<String>,
+    parser_params: Vec<(String, Vec<Param>)>,
+    detectors: Vec<String>,
+    metadata_filters: Vec<MetadataFilter>,
+    encoding_detectors: Vec<String>,
+    pub(crate) server_params: Vec<(String, String)>,
+    pub(crate) forked_jvm_args: Vec<String>,
+}
+
+impl ServerConfig {
+    /// Only uses the included parsers, instead of all parsers of the `DefaultParser`
+    pub fn include_parser<T: Into<String>>(mut self, class: T) -> Self {
+        self.included_parsers.push(class.into());
+        self
+    }
+
+    /// Doesn't use the parser of the `class`
+    pub fn exclude_parser<T: Into<String>>(mut self, class: T) -> Self {
+        self.excluded_parsers.push(class.into());
+        self
+    }
+
+    /// Adds a param to the parser of the `class`
+    pub fn parser_param<T: Into<String>>(mut self, class: T, param: Param) -> Self {
+        let class = class.into();
+        match self.parser_params.iter_mut().find(|(c, _)| *c == class) {
+            Some((_, params)) => params.push(param),
+            None => self.parser_params.push((class, vec![param])),
+        }
+        self
+    }
+
+    /// Adds the detector of the `class`, detectors are asked in the order they are added
+    pub fn detector<T: Into<String>>(mut self, class: T) -> Self {
+        self.detectors.push(class.into());
+        self
+    }
+
+    /// Adds a metadata filter, filters are applied in the order they are added
+    pub fn metadata_filter(mut self, filter: MetadataFilter) -> Self {
+        self.metadata_filters.push(filter);
+        self
+    }
+
+    /// Adds the encoding detector of the `class`, like
+    /// `org.apache.tika.parser.html.HtmlEncodingDetector`.
+    /// Encoding detectors are asked in the order they are added.
+    pub fn encoding_detector<T: Into<String>>(mut self, class: T) -> Self {
+        self.encoding_detectors.push(class.into());
+        self
+    }
+
+    /// Sets a `<server>` param of tika 2.x and later, like `taskTimeoutMillis`.
+    /// The `name` must be a valid XML element name, otherwise `to_xml` fails.
+    pub fn server_param<T: Into<String>, V: ToString>(mut self, name: T, value: V) -> Self {
+        self.server_params.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Adds a flag of the JVM of the forked process of tika 2.x and later, like `-Xmx4g`.
+    /// `ServerOptions::max_heap` and `ServerOptions::child_jvm_arg` are added here for these
+    /// versions as well.
+    pub fn forked_jvm_arg<T: Into<String>>(mut self, arg: T) -> Self {
+        self.forked_jvm_args.push(arg.into());
+        self
+    }
+
+    /// whether the `<server>` param `name` is set
+    pub(crate) fn has_server_param(&self, name: &str) -> bool {
+        self.server_params.iter().any(|(param, _)| param == name)
+    }
+
+    /// the parsers that are configured explicitly, in the order they are rendered
+    fn explicit_parsers(&self) -> Vec<&str> {
+        let mut classes: Vec<&str> = Vec::new();
+        let params = self.parser_params.iter().map(|(class, _)| class);
+        for class in self.included_parsers.iter().chain(params) {
+            if !self.excluded_parsers.contains(class) && !classes.contains(&class.as_str()) {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+
+    /// Fails if the server doesn't know a parser or detector of the config.
+    /// The `parsers` and `detectors` are the ones the server reports, see
+    /// `TikaClient::parsers` and `TikaClient::detectors`.
+    /// Metadata filters and encoding detectors aren't reported and not checked.
+    pub fn check(&self, parsers: &Parser, detectors: &Detector) -> Result<()> {
+        let mut known = HashSet::new();
+        parser_names(parsers, &mut known);
+        let params = self.parser_params.iter().map(|(class, _)| class);
+        let mut checked = HashSet::new();
+        let mut unknown: Vec<String> = self
+            .included_parsers
+            .iter()
+            .chain(&self.excluded_parsers)
+            .chain(params)
+            .filter(|class| !known.contains(class.as_str()) && checked.insert(class.as_str()))
+            .map(|class| format!("parser {}", class))
+            .collect();
+
+        let mut known = HashSet::new();
+        detector_names(detectors, &mut known);
+        unknown.extend(
+            self.detectors
+                .iter()
+                .filter(|class| !known.contains(class.as_str()) && checked.insert(class.as_str()))
+                .map(|class| format!("detector {}", class)),
+        );
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::config(format!(
+                "The tika server has no {}",
+                unknown.join(", ")
+            )))
+        }
+    }
+
+    /// The content of the `tika-config.xml`.
+    /// Fails if the name of a `server_param` is no valid XML element name.
+    pub fn to_xml(&self) -> Result<String> {
+        if let Some((name, _)) = self
+            .server_params
+            .iter()
+            .find(|(name, _)| !is_element_name(name))
+        {
+            return Err(Error::config(format!(
+                "The server param {:?} is no valid XML element name",
+                name
+            )));
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<properties>\n");
+
+        let explicit = self.explicit_parsers();
+        if !explicit.is_empty() || !self.excluded_parsers.is_empty() {
+            xml += "  <parsers>\n";
+            if self.included_parsers.is_empty() {
+                // parsers with params are configured separately and excluded from the default
+                // parser, so they aren't loaded twice
+                let _ = writeln!(xml, "    <parser class=\"{}\">", DEFAULT_PARSER);
+                let excluded = self.excluded_parsers.iter().map(String::as_str);
+                let mut seen = HashSet::new();
+                for class in excluded.chain(explicit.iter().copied()) {
+                    if seen.insert(class) {
+                        let _ =
+                            writeln!(xml, "      <parser-exclude class=\"{}\"/>", escape(class));
+                    }
+                }
+                xml += "    </parser>\n";
+            }
+            for class in explicit {
+                let params = self
+                    .parser_params
+                    .iter()
+                    .find(|(c, _)| c == class)
+                    .map(|(_, params)| params.as_slice())
+                    .unwrap_or_default();
+                write_element(&mut xml, "parser", class, params);
+            }
+            xml += "  </parsers>\n";
+        }
+
+        if !self.detectors.is_empty() {
+            xml += "  <detectors>\n";
+            for class in &self.detectors {
+                write_element(&mut xml, "detector", class, &[]);
+            }
+            xml += "  </detectors>\n";
+        }
+
+        if !self.encoding_detectors.is_empty() {
+            xml += "  <encodingDetectors>\n";
+            for class in &self.encoding_detectors {
+                write_element(&mut xml, "encodingDetector", class, &[]);
+            }
+            xml += "  </encodingDetectors>\n";
+        }
+
+        if !self.metadata_filters.is_empty() {
+            xml += "  <metadataFilters>\n";
+            for filter in &self.metadata_filters {
+                write_element(&mut xml, "metadataFilter", &filter.class, &filter.params);
+            }
+            xml += "  </metadataFilters>\n";
+        }
+
+        if !self.server_params.is_empty() || !self.forked_jvm_args.is_empty() {
+            xml += "  <server>\n    <params>\n";
+            for (name, value) in &self.server_params {
+                let _ = writeln!(xml, "      <{0}>{1}</{0}>", name, escape(value.as_str()));
+            }
+            if !self.forked_jvm_args.is_empty() {
+                xml += "      <forkedJvmArgs>\n";
+                for arg in &self.forked_jvm_args {
+                    let _ = writeln!(xml, "        <arg>{}</arg>", escape(arg.as_str()));
+                }
+                xml += "      </forkedJvmArgs>\n";
+            }
+            xml += "    </params>\n  </server>\n";
+        }
+
+        xml += "</properties>\n";
+        Ok(xml)
+    }
+
+    /// Writes the `tika-config.xml` into the `SERVER_CONFIG_DIR` of the `tika_path` and
+    /// returns its location.
+    /// The file is named after the SHA-256 hash of its content, so servers with different
+    /// configs can share the `tika_path`. The file is written to a temporary file first and
+    /// then moved into place, so concurrently starting servers never read a partial config.
+    pub(crate) fn write<P: AsRef<Path>>(&self, tika_path: P) -> Result<PathBuf> {
+        // distinguishes the temporary files of concurrent writes within this process
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        let xml = self.to_xml()?;
+        let hash = format!("{:x}", Sha256::digest(xml.as_bytes()));
+
+        let dir = tika_path.as_ref().join(SERVER_CONFIG_DIR);
+        fs::create_dir_all(&dir)?;
+        let file = dir.join(format!("tika-config-{}.xml", &hash[..16]));
+        if file.is_file() {
+            return Ok(file);
+        }
+        let tmp = with_suffix(
+            &file,
+            &format!(
+                ".tmp-{}-{}",
+                std::process::id(),
+                WRITES.fetch_add(1, Ordering::Relaxed)
+            ),
+        );
+        if let Err(e) = fs::write(&tmp, xml).and_then(|_| fs::rename(&tmp, &file)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        debug!("Wrote the tika server config to {}", file.display());
+        Ok(file)
+    }
+}
+
+/// whether the `name` can be used as XML element name, without a namespace prefix
+fn is_element_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_lowercase().starts_with("xml")
+}
+
+/// writes an element of the jvm `class` with its `params`
+fn write_element(xml: &mut String, name: &str, class: &str, params: &[Param]) {
+    if params.is_empty() {
+        let _ = writeln!(xml, "    <{} class=\"{}\"/>", name, escape(class));
+        return;
+    }
+    let _ = writeln!(xml, "    <{} class=\"{}\">", name, escape(class));
+    *xml += "      <params>\n";
+    for param in params {
+        let _ = write!(
+            xml,
+            "        <param name=\"{}\" type=\"{}\">",
+            escape(param.name.as_str()),
+            param.value.type_name()
+        );
+        let value = match &param.value {
+            ParamValue::Bool(value) => value.to_string(),
+            ParamValue::Int(value) => value.to_string(),
+            ParamValue::Long(value) => value.to_string(),
+            ParamValue::Double(value) => value.to_string(),
+            ParamValue::String(value) => escape(value.as_str()).into_owned(),
+            ParamValue::List(values) => values
+                .iter()
+                .map(|value| format!("<string>{}</string>", escape(value.as_str())))
+                .collect(),
+        };
+        *xml += &value;
+        *xml += "</param>\n";
+    }
+    *xml += "      </params>\n";
+    let _ = writeln!(xml, "    </{}>", name);
+}
+
+/// collects the class names of the `parser` and its children
+fn parser_names<'a>(parser: &'a Parser, names: &mut HashSet<&'a str>) {
+    names.insert(&parser.name);
+    for child in &parser.children {
+        parser_names(child, names);
+    }
+}
+
+/// collects the class names of the `detector` and its children
+fn detector_names<'a>(detector: &'a Detector, names: &mut HashSet<&'a str>) {
+    names.insert(&detector.name);
+    for child in &detector.children {
+        detector_names(child, names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn renders_empty_config() {
+        assert_eq!(
+            ServerConfig::default().to_xml().unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<properties>\n</properties>\n"
+        );
+    }
+
+    #[test]
+    fn excludes_parsers_with_params_from_the_default_parser() {
+        let xml = ServerConfig::default()
+            .exclude_parser("a.Exe")
+            .parser_param(PDF_PARSER, Param::new("sortByPosition", true))
+            .parser_param(PDF_PARSER, Param::new("ocrStrategy", "no_ocr"))
+            .to_xml()
+            .unwrap();
+        assert!(xml.contains(
+            "  <parsers>\n    <parser class=\"org.apache.tika.parser.DefaultParser\">\n      \
+             <parser-exclude class=\"a.Exe\"/>\n      \
+             <parser-exclude class=\"org.apache.tika.parser.pdf.PDFParser\"/>\n    </parser>\n    \
+             <parser class=\"org.apache.tika.parser.pdf.PDFParser\">\n      <params>\n        \
+             <param name=\"sortByPosition\" type=\"bool\">true</param>\n        \
+             <param name=\"ocrStrategy\" type=\"string\">no_ocr</param>\n      </params>\n    \
+             </parser>\n  </parsers>\n"
+        ));
+    }
+
+    #[test]
+    fn renders_included_parsers_without_default_parser() {
+        let xml = ServerConfig::default()
+            .include_parser("a.Txt")
+            .include_parser("a.Html")
+            .exclude_parser("a.Html")
+            .to_xml()
+            .unwrap();
+        assert!(!xml.contains(DEFAULT_PARSER));
+        assert!(xml.contains("    <parser class=\"a.Txt\"/>\n"));
+        assert!(!xml.contains("a.Html"));
+    }
+
+    #[test]
+    fn escapes_values() {
+        let xml = ServerConfig::default()
+            .detector("a.Detector<&>")
+            .metadata_filter(MetadataFilter::new("a.Filter").param(Param::new(
+                "include",
+                vec!["dc:title".to_string(), "a&b".to_string()],
+            )))
+            .server_param("cors", "<origin>")
+            .to_xml()
+            .unwrap();
+        assert!(xml.contains("<detector class=\"a.Detector&lt;&amp;&gt;\"/>"));
+        assert!(xml.contains(
+            "<param name=\"include\" type=\"list\">\
+             <string>dc:title</string><string>a&amp;b</string></param>"
+        ));
+        assert!(xml.contains("  <server>\n    <params>\n      <cors>&lt;origin&gt;</cors>\n"));
+    }
+
+    #[test]
+    fn renders_forked_jvm_args() {
+        let xml = ServerConfig::default()
+            .server_param("maxFiles", 100)
+            .forked_jvm_arg("-Xmx4g")
+            .to_xml()
+            .unwrap();
+        assert!(xml.ends_with(
+            "  <server>\n    <params>\n      <maxFiles>100</maxFiles>\n      \
+             <forkedJvmArgs>\n        <arg>-Xmx4g</arg>\n      </forkedJvmArgs>\n    \
+             </params>\n  </server>\n</properties>\n"
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_server_param_names() {
+        for name in &["", "a b", "1port", "x></params><y", "ns:name", "xmlThing"] {
+            let config = ServerConfig::default().server_param(*name, 1);
+            assert!(config.to_xml().is_err(), "{:?}", name);
+        }
+        let config = ServerConfig::default().server_param("taskTimeoutMillis", 1000);
+        assert!(config
+            .to_xml()
+            .unwrap()
+            .contains("<taskTimeoutMillis>1000</taskTimeoutMillis>"));
+    }
+
+    #[test]
+    fn writes_config_named_after_its_content() {
+        let dir = scratch_dir("config");
+        let config = ServerConfig::default().exclude_parser("a.Exe");
+        let file = config.write(&dir).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), config.to_xml().unwrap());
+        assert_eq!(config.write(&dir).unwrap(), file);
+        let other = ServerConfig::default().write(&dir).unwrap();
+        assert_ne!(other, file);
+        let files = fs::read_dir(dir.join(SERVER_CONFIG_DIR)).unwrap().count();
+        assert_eq!(files, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+use crate::client::TikaServerFile;
+use crate::server::cache::jar_version;
+use crate::server::jar::{major_version, JarManifest};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The executables of tika server installations, that are looked up in `PATH`
+pub const EXECUTABLES: &[&str] = &["tika-server", "tika-rest-server"];
+
+/// The dirs tika server jars are commonly installed to
+pub const SEARCH_DIRS: &[&str] = &[
+    "/usr/share/java",
+    "/usr/share/tika",
+    "/usr/local/share/java",
+    "/usr/local/tika",
+    "/opt/tika",
+    "/opt/tika-server",
+    "/usr/local/opt/tika/libexec",
+    "/opt/homebrew/opt/tika/libexec",
+];
+
+/// The pattern the file names of tika server jars match
+pub const JAR_PATTERN: &str = "tika-server*.jar";
+
+/// Scripts larger than this are not searched for the jar they launch
+const MAX_SCRIPT_LEN: u64 = 64 * 1024;
+
+/// A tika server installed on the system
+#[derive(Debug, Clone)]
+pub struct InstalledServer {
+    /// the executable or jar of the server
+    pub file: TikaServerFile,
+    /// the tika version of the server, like `2.9.2`, if it could be detected
+    pub version: Option<String>,
+}
+
+impl InstalledServer {
+    /// whether the server is of the tika `version`
+    pub fn is_version(&self, version: &str) -> bool {
+        self.version.as_deref() == Some(version.trim())
+    }
+}
+
+/// Finds the tika servers installed on the system
+///
+/// # Example
+///
+/// ```edition2018
+/// use rustika::server::discovery::Discovery;
+///
+/// let servers = Discovery::default().dir("/srv/tika").find();
+/// let matching = servers.iter().find(|server| server.is_version("2.9.2"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    executables: Vec<String>,
+    dirs: Vec<PathBuf>,
+    patterns: Vec<String>,
+}
+
+impl Default for Discovery {
+    /// Looks for the `EXECUTABLES` in `PATH` and for jars matching the `JAR_PATTERN` in the
+    /// `SEARCH_DIRS`
+    fn default() -> Self {
+        Discovery {
+            executables: EXECUTABLES.iter().map(|s| s.to_string()).collect(),
+            dirs: SEARCH_DIRS.iter().map(PathBuf::from).collect(),
+            patterns: vec![JAR_PATTERN.to_string()],
+        }
+    }
+}
+
+impl Discovery {
+    /// Looks for nothing, until executables, dirs and patterns are added
+    pub fn empty() -> Self {
+        Discovery {
+            executables: Vec::new(),
+            dirs: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Also looks for the executable `name` in `PATH`
+    pub fn executable<T: Into<String>>(mut self, name: T) -> Self {
+        self.executables.push(name.into());
+        self
+    }
+
+    /// Also looks for jars in the `dir`
+    pub fn dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.dirs.push(dir.as_ref().into());
+        self
+    }
+
+    /// Also matches jars against the glob `pattern`, like `tika-server*.jar`.
+    /// `*` matches any number and `?` a single character.
+    pub fn pattern<T: Into<String>>(mut self, pattern: T) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Every tika server found, executables first.
+    /// Files that are found several times, e.g. through symlinks, are only returned once.
+    pub fn find(&self) -> Vec<InstalledServer> {
+        let mut seen = HashSet::new();
+        let mut servers = Vec::new();
+
+        for name in &self.executables {
+            for path in path_executables(name) {
+                if seen.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                    servers.push(InstalledServer {
+                        version: executable_version(&path),
+                        file: TikaServerFile::PathExecutable(path),
+                    });
+                }
+            }
+        }
+
+        for dir in &self.dirs {
+            let mut jars: Vec<PathBuf> = match fs::read_dir(dir) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.is_file() && self.matches(path))
+                    .collect(),
+                Err(_) => continue,
+            };
+            jars.sort();
+            for path in jars {
+                if seen.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                    servers.push(InstalledServer {
+                        version: installed_jar_version(&path),
+                        file: TikaServerFile::SystemJar(path),
+                    });
+                }
+            }
+        }
+        servers
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return false,
+        };
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(pattern.as_bytes(), name.as_bytes()))
+    }
+}
+
+/// every executable `name` in `PATH`, in the order of `PATH`
+fn path_executables(name: &str) -> Vec<PathBuf> {
+    let paths = env::var_os("PATH").unwrap_or_default();
+    env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .filter(|path| which::which_in(path, None::<&str>, "/").is_ok())
+        .collect()
+}
+
+/// the version of an installed jar, from its manifest or its file name.
+/// Symlinks like `/usr/share/java/tika-server.jar` are followed.
+fn installed_jar_version(jar: &Path) -> Option<String> {
+    JarManifest::read(jar)
+        .ok()
+        .and_then(|manifest| manifest.version)
+        .or_else(|| jar_version(jar))
+        .or_else(|| jar_version(&fs::canonicalize(jar).ok()?))
+}
+
+/// The version of an installed executable.
+/// Launcher scripts, like the one of homebrew, are searched for the jar they launch,
+/// otherwise the version is taken from the install dir, like `Cellar/tika/2.9.2/bin`.
+pub(crate) fn executable_version(executable: &Path) -> Option<String> {
+    let path = fs::canonicalize(executable).unwrap_or_else(|_| executable.into());
+    let script = fs::metadata(&path)
+        .ok()
+        .filter(|meta| meta.len() <= MAX_SCRIPT_LEN)
+        .and_then(|_| fs::read_to_string(&path).ok());
+    if let Some(script) = script {
+        let version = script
+            .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '=' | ':'))
+            .filter(|token| token.ends_with(".jar"))
+            .map(|token| script_path(token, &path))
+            .find_map(|jar| installed_jar_version(&jar));
+        if version.is_some() {
+            return version;
+        }
+    }
+    path.ancestors()
+        .filter_map(|dir| dir.file_name()?.to_str())
+        .find(|name| major_version(name).is_some() && name.contains('.'))
+        .map(str::to_string)
+}
+
+/// a path in the `script`, relative paths are resolved against its dir
+fn script_path(token: &str, script: &Path) -> PathBuf {
+    let path = Path::new(token);
+    match script.parent() {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.into(),
+    }
+}
+
+/// whether the `name` matches the glob `pattern`
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // where to continue, if the last `*` should match one more character
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{scratch_dir, write_file};
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    }
+
+    #[test]
+    fn matches_literal_names() {
+        assert!(matches("tika-server.jar", "tika-server.jar"));
+        assert!(!matches("tika-server.jar", "tika-server.jar.sha1"));
+        assert!(!matches("tika-server.jar", "tika-server.ja"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches(JAR_PATTERN, "tika-server.jar"));
+        assert!(matches(JAR_PATTERN, "tika-server-standard-2.9.2.jar"));
+        assert!(!matches(JAR_PATTERN, "tika-app-2.9.2.jar"));
+        assert!(!matches(JAR_PATTERN, "tika-server-2.9.2.jar.sha512"));
+        assert!(matches("*", ""));
+        assert!(matches("**", "anything"));
+        assert!(matches("a*", "a"));
+        assert!(!matches("a*", "ba"));
+        assert!(matches("*a", "aaa"));
+        assert!(!matches("*a", "aab"));
+    }
+
+    #[test]
+    fn matches_single_characters() {
+        assert!(matches("tika-?.jar", "tika-1.jar"));
+        assert!(!matches("tika-?.jar", "tika-.jar"));
+        assert!(!matches("tika-?.jar", "tika-10.jar"));
+        assert!(matches("?*", "a"));
+        assert!(!matches("?*", ""));
+    }
+
+    #[test]
+    fn backtracks_to_the_last_wildcard() {
+        assert!(matches("a*b*c", "abbbc"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYcZ"));
+        assert!(matches("*.jar", "x.jar.jar"));
+        assert!(matches("*ab*", "aaab"));
+        assert!(!matches("*ab", "aba"));
+    }
+
+    #[test]
+    fn versions_of_launched_jars() {
+        let dir = scratch_dir("discovery-jars");
+        let absolute = write_file(
+            &dir,
+            "absolute/tika-server",
+            "#!/bin/sh\nexec java -jar \"/opt/tika/tika-server-standard-2.9.2.jar\" \"$@\"\n",
+        );
+        assert_eq!(executable_version(&absolute), Some("2.9.2".to_string()));
+
+        let relative = write_file(
+            &dir,
+            "relative/bin/tika-server",
+            "#!/bin/sh\nJAR='../libexec/tika-server-1.28.5.jar'\nexec java -jar $JAR\n",
+        );
+        assert_eq!(executable_version(&relative), Some("1.28.5".to_string()));
+
+        let assigned = write_file(
+            &dir,
+            "assigned/tika-server",
+            "set TIKA_JAR=C:tika-server-full-3.0.0.jar\r\n",
+        );
+        assert_eq!(executable_version(&assigned), Some("3.0.0".to_string()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn versions_of_install_dirs() {
+        let dir = scratch_dir("discovery-install-dirs");
+        // homebrew launches the jar by a variable, its version is in the path of the cellar
+        let brew = write_file(
+            &dir,
+            "Cellar/tika/2.9.2/bin/tika-rest-server",
+            "#!/bin/bash\nexec \"$JAVA\" -jar \"$TIKA_HOME/tika.jar\" \"$@\"\n",
+        );
+        assert_eq!(executable_version(&brew), Some("2.9.2".to_string()));
+
+        let unknown = write_file(&dir, "bin/tika-server", "#!/bin/sh\nexec tika \"$@\"\n");
+        assert_eq!(executable_version(&unknown), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn skips_large_executables() {
+        let dir = scratch_dir("discovery-large");
+        let mut content = "tika-server-2.9.2.jar ".to_string();
+        content.push_str(&"x".repeat(MAX_SCRIPT_LEN as usize));
+        let binary = write_file(&dir, "bin/tika-server", &content);
+        assert_eq!(executable_version(&binary), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn versions_of_linked_jars() {
+        let dir = scratch_dir("discovery-links");
+        let jar = write_file(&dir, "lib/tika-server-standard-2.9.1.jar", "not a jar");
+        std::os::unix::fs::symlink(&jar, dir.join("tika-server.jar")).unwrap();
+        let launcher = write_file(&dir, "tika-server", "exec java -jar tika-server.jar\n");
+        assert_eq!(executable_version(&launcher), Some("2.9.1".to_string()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,538 @@
+use crate::client::error_for_status;
+use crate::error::{Error, Result};
+use crate::server::cache::{checksum_status, jar_version, ChecksumStatus};
+use fs2::FileExt;
+use reqwest::blocking;
+use reqwest::StatusCode;
+use sha2::digest::DynDigest;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// The maven repository the tika server jar is downloaded from by default
+pub const DEFAULT_MAVEN_REPOSITORY: &str = "https://repo1.maven.org/maven2";
+
+/// The hash algorithm of a checksum maven publishes next to an artifact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    /// the `.sha512` checksum
+    Sha512,
+    /// the `.sha1` checksum
+    Sha1,
+}
+
+impl ChecksumAlgorithm {
+    /// the file extension of the checksum
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha512 => "sha512",
+            ChecksumAlgorithm::Sha1 => "sha1",
+        }
+    }
+
+    /// the known algorithms, the preferred first
+    pub(crate) fn all() -> &'static [ChecksumAlgorithm] {
+        &[ChecksumAlgorithm::Sha512, ChecksumAlgorithm::Sha1]
+    }
+
+    fn hasher(&self) -> Box<dyn DynDigest> {
+        match self {
+            ChecksumAlgorithm::Sha512 => Box::new(sha2::Sha512::default()),
+            ChecksumAlgorithm::Sha1 => Box::new(sha1::Sha1::default()),
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChecksumAlgorithm::Sha512 => f.write_str("SHA-512"),
+            ChecksumAlgorithm::Sha1 => f.write_str("SHA-1"),
+        }
+    }
+}
+
+/// Downloads the jar at `url` to `dest`, unless it was downloaded before.
+/// Besides `http(s)://` urls, jars of local repositories are copied from `file://` urls.
+///
+/// The jar is checked against the checksum published next to it, preferring `.sha512` over
+/// `.sha1`, and only moved to `dest` if it matches. The verified checksum is kept next to
+/// `dest`, like `dest.sha512`. A jar at `dest` that doesn't match its kept checksum or has
+/// none is downloaded again.
+/// Concurrent downloads to the same `dest`, also from other processes, are serialized by a
+/// lock on `dest.lock`.
+pub(crate) fn download_jar(url: &str, dest: &Path) -> Result<()> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let lock = open_lock(dest)?;
+    lock.lock_exclusive()?;
+    let downloaded = download_locked(url, dest);
+    let _ = lock.unlock();
+    downloaded
+}
+
+fn download_locked(url: &str, dest: &Path) -> Result<()> {
+    // the jar is only moved into place after it was verified, but may have changed since
+    if dest.exists() {
+        match checksum_status(dest)? {
+            ChecksumStatus::Verified(_) => {
+                debug!("Using previously downloaded {}", dest.display());
+                return Ok(());
+            }
+            status => {
+                warn!(
+                    "Downloading {} again, it doesn't match the checksum kept next to it: {:?}",
+                    dest.display(),
+                    status
+                );
+                remove_jar(dest)?;
+            }
+        }
+    }
+
+    // the jars are too large for the default timeout
+    let client = blocking::Client::builder().timeout(None).build()?;
+    let (algorithm, expected) = fetch_checksum(&client, url)?;
+
+    debug!("Downloading tika server jar {} to {}", url, dest.display());
+    let part = with_suffix(dest, &format!(".part-{}", std::process::id()));
+    let written = fetch_to(&client, url, &part, algorithm);
+    let actual = match written {
+        Ok(actual) => actual,
+        Err(e) => {
+            let _ = fs::remove_file(&part);
+            return Err(e);
+        }
+    };
+    if !actual.eq_ignore_ascii_case(&expected) {
+        let _ = fs::remove_file(&part);
+        return Err(Error::checksum(url, algorithm, expected, actual));
+    }
+    fs::write(checksum_file(dest, algorithm), &actual)?;
+    fs::rename(&part, dest)?;
+    debug!("Finished download to {}", dest.display());
+    Ok(())
+}
+
+/// removes the `jar` and the checksums kept next to it
+fn remove_jar(jar: &Path) -> io::Result<()> {
+    for algorithm in ChecksumAlgorithm::all() {
+        match fs::remove_file(checksum_file(jar, *algorithm)) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            removed => removed?,
+        }
+    }
+    fs::remove_file(jar)
+}
+
+/// the published checksum of the artifact at `url`
+fn fetch_checksum(client: &blocking::Client, url: &str) -> Result<(ChecksumAlgorithm, String)> {
+    for algorithm in ChecksumAlgorithm::all() {
+        let checksum_url = format!("{}.{}", url, algorithm.extension());
+        if let Some(mut checksum_file) = open(client, &checksum_url)? {
+            // the file either contains just the hex digest or the digest and the file name
+            let mut checksum = String::new();
+            checksum_file.read_to_string(&mut checksum)?;
+            let checksum = checksum.split_whitespace().next().unwrap_or_default();
+            return Ok((*algorithm, checksum.to_lowercase()));
+        }
+    }
+    Err(Error::config(format!(
+        "No checksum is published for {}",
+        url
+    )))
+}
+
+/// writes the artifact at `url` to `path` and returns its hex digest
+fn fetch_to(
+    client: &blocking::Client,
+    url: &str,
+    path: &Path,
+    algorithm: ChecksumAlgorithm,
+) -> Result<String> {
+    let mut resp = open(client, url)?
+        .ok_or_else(|| Error::config(format!("The tika server jar {} does not exist", url)))?;
+    let mut out = fs::File::create(path)?;
+    let digest = copy_digest(&mut resp, &mut out, algorithm)?;
+    out.sync_all()?;
+    Ok(digest)
+}
+
+/// the hex digest of the file at `path`
+pub(crate) fn file_digest(path: &Path, algorithm: ChecksumAlgorithm) -> Result<String> {
+    copy_digest(&mut fs::File::open(path)?, &mut io::sink(), algorithm)
+}
+
+/// copies `from` into `to` and returns the hex digest of the copied bytes
+fn copy_digest<R: Read + ?Sized, W: Write>(
+    from: &mut R,
+    to: &mut W,
+    algorithm: ChecksumAlgorithm,
+) -> Result<String> {
+    let mut hasher = algorithm.hasher();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = match from.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buf[..n]);
+        to.write_all(&buf[..n])?;
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// opens the resource at the `http(s)://` or `file://` `url`, `None` if it doesn't exist
+fn open(client: &blocking::Client, url: &str) -> Result<Option<Box<dyn Read>>> {
+    let parsed = Url::parse(url)?;
+    if parsed.scheme() == "file" {
+        let path = parsed
+            .to_file_path()
+            .map_err(|_| Error::config(format!("{} is not a valid file url", url)))?;
+        return match fs::File::open(path) {
+            Ok(file) => Ok(Some(Box::new(file))),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+    }
+    match error_for_status(client.get(parsed).send()?) {
+        Ok(resp) => Ok(Some(Box::new(resp))),
+        Err(ref e) if e.status_code() == Some(StatusCode::NOT_FOUND) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The file name the jar at `url` is stored as, like `tika-server-standard-2.9.2.jar`.
+///
+/// The name is taken from the last path segment of the `url` or, for urls like
+/// `remotecontent?filepath=org/apache/tika/...`, from its query. Names without a version,
+/// like `tika-server.jar`, get a digest of the `url`, like `tika-server.3f2a9c1d0b4e5f6a.jar`,
+/// so that the jars of different urls don't share the file.
+pub(crate) fn jar_file_name(url: &str) -> String {
+    let is_file_name = |name: &str| {
+        name.ends_with(".jar")
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    };
+    let names: Vec<_> = Url::parse(url)
+        .ok()
+        .map(|url| {
+            let segment = url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(str::to_string);
+            segment
+                .into_iter()
+                .chain(url.query_pairs().map(|(_, value)| value.into_owned()))
+                .filter_map(|name| name.rsplit('/').next().map(str::to_string))
+                .filter(|name| is_file_name(name))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(name) = names
+        .iter()
+        .find(|name| jar_version(Path::new(name)).is_some())
+    {
+        return name.clone();
+    }
+    let stem = names
+        .first()
+        .and_then(|name| name.strip_suffix(".jar"))
+        .unwrap_or("tika-server");
+    let digest = Sha256::digest(url.as_bytes());
+    let digest: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}.{}.jar", stem, digest)
+}
+
+/// the file the verified checksum of the `jar` is kept in, like `jar.sha512`
+pub(crate) fn checksum_file(jar: &Path, algorithm: ChecksumAlgorithm) -> PathBuf {
+    with_suffix(jar, &format!(".{}", algorithm.extension()))
+}
+
+/// the lock file that serializes downloads to `dest`
+pub(crate) fn open_lock(dest: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(with_suffix(dest, ".lock"))
+}
+
+/// `path` with the `suffix` appended to its file name
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::{repository, scratch_dir, serve};
+    use sha2::Digest;
+
+    const JAR: &[u8] = b"PK not really a jar";
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn sha512(content: &[u8]) -> String {
+        hex(&sha2::Sha512::digest(content))
+    }
+
+    fn sha1(content: &[u8]) -> String {
+        hex(&sha1::Sha1::digest(content))
+    }
+
+    /// the files left in the directory of `dest`
+    fn files(dest: &Path) -> Vec<String> {
+        let mut files: Vec<_> = fs::read_dir(dest.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn names_jars_after_their_url() {
+        assert_eq!(
+            jar_file_name("https://repo1.maven.org/maven2/org/apache/tika/tika-server-standard/2.9.2/tika-server-standard-2.9.2.jar"),
+            "tika-server-standard-2.9.2.jar"
+        );
+        assert_eq!(
+            jar_file_name("https://search.maven.org/remotecontent?filepath=org/apache/tika/tika-server/1.28.5/tika-server-1.28.5.jar"),
+            "tika-server-1.28.5.jar"
+        );
+    }
+
+    #[test]
+    fn names_jars_of_unversioned_urls_by_digest() {
+        let first = jar_file_name("https://mirror.example.com/tika/latest/tika-server.jar");
+        let second = jar_file_name("file:///opt/repository/tika-server.jar");
+        assert_ne!(first, second);
+        assert_eq!(
+            first,
+            jar_file_name("https://mirror.example.com/tika/latest/tika-server.jar")
+        );
+        let third = jar_file_name("https://example.com/download?id=5");
+        assert!(third.starts_with("tika-server.") && third.len() == "tika-server..jar".len() + 16);
+        for name in [&first, &second] {
+            assert!(name.starts_with("tika-server.") && name.ends_with(".jar"));
+            assert_eq!(jar_version(Path::new(name)), None);
+        }
+    }
+
+    #[test]
+    fn downloads_unversioned_jars_of_different_urls_to_different_files() {
+        let repo = serve(vec![
+            ("/latest/tika-server.jar", JAR.to_vec()),
+            ("/latest/tika-server.jar.sha512", sha512(JAR).into_bytes()),
+            ("/nightly/tika-server.jar", b"nightly".to_vec()),
+            (
+                "/nightly/tika-server.jar.sha512",
+                sha512(b"nightly").into_bytes(),
+            ),
+        ]);
+        let dir = scratch_dir("download-unversioned");
+        let mut jars = Vec::new();
+        for channel in ["latest", "nightly"] {
+            let url = format!("{}{}/tika-server.jar", repo, channel);
+            let dest = dir.join(jar_file_name(&url));
+            download_jar(&url, &dest).unwrap();
+            jars.push(dest);
+        }
+        assert_ne!(jars[0], jars[1]);
+        assert_eq!(fs::read(&jars[0]).unwrap(), JAR);
+        assert_eq!(fs::read(&jars[1]).unwrap(), b"nightly");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn downloads_jars_with_matching_checksum() {
+        let repo = serve(vec![
+            ("/tika.jar", JAR.to_vec()),
+            ("/tika.jar.sha512", sha512(JAR).to_uppercase().into_bytes()),
+        ]);
+        let dir = scratch_dir("download-http-match");
+        let dest = dir.join("tika.jar");
+        download_jar(&format!("{}tika.jar", repo), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), JAR);
+        assert_eq!(
+            fs::read_to_string(checksum_file(&dest, ChecksumAlgorithm::Sha512)).unwrap(),
+            sha512(JAR)
+        );
+        assert_eq!(
+            files(&dest),
+            vec!["tika.jar", "tika.jar.lock", "tika.jar.sha512"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn downloads_changed_jars_again() {
+        let repo = serve(vec![
+            ("/tika.jar", JAR.to_vec()),
+            ("/tika.jar.sha512", sha512(JAR).into_bytes()),
+        ]);
+        let dir = scratch_dir("download-changed");
+        let dest = dir.join("tika.jar");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&dest, b"corrupt").unwrap();
+        // the checksum of the jar, before it changed
+        fs::write(checksum_file(&dest, ChecksumAlgorithm::Sha1), sha1(b"jar")).unwrap();
+        download_jar(&format!("{}tika.jar", repo), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), JAR);
+        assert_eq!(
+            files(&dest),
+            vec!["tika.jar", "tika.jar.lock", "tika.jar.sha512"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn downloads_jars_without_kept_checksum_again() {
+        let repo = serve(vec![
+            ("/tika.jar", JAR.to_vec()),
+            ("/tika.jar.sha512", sha512(JAR).into_bytes()),
+        ]);
+        let dir = scratch_dir("download-unverified");
+        let dest = dir.join("tika.jar");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&dest, b"an older jar").unwrap();
+        download_jar(&format!("{}tika.jar", repo), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), JAR);
+        assert_eq!(
+            files(&dest),
+            vec!["tika.jar", "tika.jar.lock", "tika.jar.sha512"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn falls_back_to_sha1_checksums() {
+        let repo = serve(vec![
+            ("/tika.jar", JAR.to_vec()),
+            (
+                "/tika.jar.sha1",
+                format!("{}  tika.jar\n", sha1(JAR)).into_bytes(),
+            ),
+        ]);
+        let dir = scratch_dir("download-http-sha1");
+        let dest = dir.join("tika.jar");
+        download_jar(&format!("{}tika.jar", repo), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), JAR);
+        assert!(checksum_file(&dest, ChecksumAlgorithm::Sha1).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_jars_with_mismatching_checksum() {
+        let repo = serve(vec![
+            ("/tika.jar", JAR.to_vec()),
+            ("/tika.jar.sha512", sha512(b"another jar").into_bytes()),
+        ]);
+        let dir = scratch_dir("download-http-mismatch");
+        let dest = dir.join("tika.jar");
+        let err = download_jar(&format!("{}tika.jar", repo), &dest).unwrap_err();
+        match err.kind() {
+            ErrorKind::Checksum {
+                algorithm,
+                expected,
+                actual,
+                ..
+            } => {
+                assert_eq!(*algorithm, ChecksumAlgorithm::Sha512);
+                assert_eq!(expected, &sha512(b"another jar"));
+                assert_eq!(actual, &sha512(JAR));
+            }
+            kind => panic!("unexpected error {:?}", kind),
+        }
+        assert_eq!(files(&dest), vec!["tika.jar.lock"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_jars_without_checksum() {
+        let repo = serve(vec![("/tika.jar", JAR.to_vec())]);
+        let dir = scratch_dir("download-http-missing");
+        let dest = dir.join("tika.jar");
+        let err = download_jar(&format!("{}tika.jar", repo), &dest).unwrap_err();
+        assert!(err.to_string().contains("No checksum is published"));
+        assert_eq!(files(&dest), vec!["tika.jar.lock"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_jars() {
+        let repo = serve(vec![("/tika.jar.sha512", sha512(JAR).into_bytes())]);
+        let dir = scratch_dir("download-http-no-jar");
+        let dest = dir.join("tika.jar");
+        let err = download_jar(&format!("{}tika.jar", repo), &dest).unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+        assert_eq!(files(&dest), vec!["tika.jar.lock"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copies_jars_of_local_repositories() {
+        let (repo, url) = repository(
+            "download-repo-match",
+            vec![
+                ("tika.jar", JAR.to_vec()),
+                ("tika.jar.sha512", sha512(JAR).into_bytes()),
+            ],
+        );
+        let dir = scratch_dir("download-file-match");
+        let dest = dir.join("tika.jar");
+        download_jar(&format!("{}tika.jar", url), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), JAR);
+        // the jar isn't fetched again
+        fs::remove_dir_all(&repo).unwrap();
+        download_jar(&format!("{}tika.jar", url), &dest).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_local_jars_with_mismatching_checksum() {
+        let (repo, url) = repository(
+            "download-repo-mismatch",
+            vec![
+                ("tika.jar", JAR.to_vec()),
+                ("tika.jar.sha1", sha1(b"another jar").into_bytes()),
+            ],
+        );
+        let dir = scratch_dir("download-file-mismatch");
+        let dest = dir.join("tika.jar");
+        let err = download_jar(&format!("{}tika.jar", url), &dest).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Checksum { .. }));
+        assert!(!dest.exists());
+        fs::remove_dir_all(&repo).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_local_jars_without_checksum() {
+        let (repo, url) = repository("download-repo-missing", vec![("tika.jar", JAR.to_vec())]);
+        let dir = scratch_dir("download-file-missing");
+        let dest = dir.join("tika.jar");
+        let err = download_jar(&format!("{}tika.jar", url), &dest).unwrap_err();
+        assert!(err.to_string().contains("No checksum is published"));
+        assert!(!dest.exists());
+        fs::remove_dir_all(&repo).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+#[cfg(feature = "async")]
+use crate::async_client::AsyncTikaClient;
+use crate::client::{launch_server, stop_server, TikaClient, TikaConfig};
+use crate::error::Result;
+use crate::server::logs::ServerLogs;
+use crate::server::{self, ServerProcess, DEFAULT_SHUTDOWN_TIMEOUT};
+use reqwest::{blocking, Url};
+use std::process::{Child, ExitStatus};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// A tika server, either spawned or attached to, that any number of clients share.
+///
+/// The `TikaServer` is cheap to clone. A spawned server is shut down once the `TikaServer`,
+/// its clones and all clients created from it are dropped. Dropped within a tokio runtime,
+/// the server is shut down on a blocking thread, use `AsyncTikaClient::shutdown` to wait
+/// for it.
+///
+/// # Example
+///
+/// Share a single server between threads
+///
+/// ```edition2018,no_run
+/// # fn run() -> rustika::Result<()> {
+/// use rustika::{TikaBuilder, TikaServer};
+/// use std::thread;
+///
+/// let server = TikaServer::start(TikaBuilder::default())?;
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let client = server.client();
+///         thread::spawn(move || client.extract_text("some text"))
+///     })
+///     .collect();
+/// for worker in workers {
+///     println!("{}", worker.join().unwrap()?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TikaServer {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: TikaConfig,
+    endpoint: Url,
+    /// the spawned process, `None` if the server was attached to
+    process: ServerProcess,
+    logs: ServerLogs,
+    /// the http client the blocking clients share
+    client: OnceLock<blocking::Client>,
+    /// the http client the async clients share
+    #[cfg(feature = "async")]
+    async_client: OnceLock<reqwest::Client>,
+}
+
+impl TikaServer {
+    /// Spawns the tika server of the `config` and waits until it serves requests.
+    /// With `SpawnMode::AttachOrSpawn`, a tika server that already answers is attached to
+    /// instead, other services listening at the address are not.
+    pub fn start(mut config: TikaConfig) -> Result<Self> {
+        let attaches = config.server_policy.attaches();
+        let endpoint = config.tika_mode.server_endpoint();
+        // only a tika server is attached to, not any service that listens at the endpoint
+        let probe = blocking::Client::new();
+        let is_tika = || match server::tika_version(&probe, &endpoint) {
+            Some(version) => {
+                debug!("{} answers at {}", version, endpoint);
+                true
+            }
+            None => false,
+        };
+        let logs = ServerLogs::new(config.server_log_lines);
+        if attaches && is_tika() {
+            info!("Attaching to the running tika server at {}", endpoint);
+            return Ok(TikaServer::new(config, None, logs));
+        }
+        match launch_server(&mut config, &logs) {
+            Ok(handle) => Ok(TikaServer::new(config, Some(handle), logs)),
+            // another process may have spawned a server at the same address in the meantime
+            Err(e) if attaches && is_tika() => {
+                info!(
+                    "Attaching to the tika server at {}, that was started concurrently: {}",
+                    endpoint, e
+                );
+                Ok(TikaServer::new(config, None, logs))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn new(config: TikaConfig, handle: Option<Child>, logs: ServerLogs) -> Self {
+        let process = ServerProcess::default();
+        *process.lock() = handle;
+        TikaServer {
+            inner: Arc::new(Inner {
+                endpoint: config.tika_mode.server_endpoint(),
+                config,
+                process,
+                logs,
+                client: OnceLock::new(),
+                #[cfg(feature = "async")]
+                async_client: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// A new client of the server, that shares the connections of the other clients
+    pub fn client(&self) -> TikaClient {
+        let client = self.inner.client.get_or_init(blocking::Client::new).clone();
+        TikaClient::shared(self.clone(), client)
+    }
+
+    /// A new async client of the server, that shares the connections of the other async
+    /// clients
+    #[cfg(feature = "async")]
+    pub fn async_client(&self) -> AsyncTikaClient {
+        let client = self
+            .inner
+            .async_client
+            .get_or_init(reqwest::Client::new)
+            .clone();
+        AsyncTikaClient::shared(self.clone(), client)
+    }
+
+    /// the config the server was started with
+    pub fn config(&self) -> &TikaConfig {
+        &self.inner.config
+    }
+
+    /// the endpoint of the server
+    pub fn endpoint(&self) -> &Url {
+        &self.inner.endpoint
+    }
+
+    /// The last lines the spawned server logged, oldest first
+    pub fn logs(&self) -> Vec<String> {
+        self.inner.logs.lines()
+    }
+
+    /// whether the handle is currently running a spawned server, `false` if the server was
+    /// attached to or shut down
+    pub fn is_spawned(&self) -> bool {
+        self.inner.process.lock().is_some()
+    }
+
+    /// whether the spawned server is still running, `None` if the server was attached to or
+    /// shut down
+    pub fn is_running(&self) -> Option<bool> {
+        self.inner.process.is_alive()
+    }
+
+    /// Shuts down the spawned server for all its clients and returns its exit status, `None`
+    /// if the server was attached to or shut down before.
+    /// The server is asked to terminate and killed, if it didn't exit within the `timeout`.
+    pub fn shutdown(&self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        stop_server(
+            &mut self.inner.process.lock(),
+            &self.inner.endpoint,
+            timeout,
+        )
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // shut down the spawned server
+        let stop = self.process.get_mut().take().map(|child| StopServer {
+            process: Some(child),
+            endpoint: self.endpoint.clone(),
+        });
+        // the blocking http client waits for its own runtime to shut down, when dropped
+        let client = self.client.take();
+        if stop.is_none() && client.is_none() {
+            return;
+        }
+        // waiting for the server to exit must not block a worker of the runtime, the runtime
+        // waits for the blocking task when it shuts down
+        #[cfg(feature = "async")]
+        {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn_blocking(move || drop((stop, client)));
+                return;
+            }
+        }
+        drop((stop, client));
+    }
+}
+
+/// Stops the spawned server when dropped, also if a runtime drops it without running the
+/// blocking task
+struct StopServer {
+    process: Option<Child>,
+    endpoint: Url,
+}
+
+impl Drop for StopServer {
+    fn drop(&mut self) {
+        if let Err(e) = stop_server(&mut self.process, &self.endpoint, DEFAULT_SHUTDOWN_TIMEOUT) {
+            error!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ServerPolicy, SpawnMode, TikaServerFile, TikaServerFileLocation};
+    use crate::test_util::{scratch_dir, serve, write_file};
+    use crate::TikaBuilder;
+    use std::net::SocketAddr;
+
+    /// starts a server at the address of the `endpoint`, attaching to a running one
+    fn attach(endpoint: &Url) -> Result<TikaServer> {
+        let addr: SocketAddr = format!(
+            "{}:{}",
+            endpoint.host_str().unwrap(),
+            endpoint.port().unwrap()
+        )
+        .parse()
+        .unwrap();
+        // a jar that can't be launched, the address is taken anyway
+        let jar = write_file(
+            &scratch_dir(&format!("handle-{}", addr.port())),
+            "tika-server.jar",
+            "",
+        );
+        let policy = ServerPolicy::default()
+            .addr(addr)
+            .spawn_mode(SpawnMode::AttachOrSpawn)
+            .download_missing_jar(false);
+        TikaServer::start(
+            TikaBuilder::default()
+                .server_policy(policy)
+                .server_file(TikaServerFileLocation::File(TikaServerFile::Download(jar))),
+        )
+    }
+
+    #[test]
+    fn attaches_to_tika_servers() {
+        let endpoint = serve(vec![("/version", b"Apache Tika 2.9.1".to_vec())]);
+        let server = attach(&endpoint).unwrap();
+        assert!(!server.is_spawned());
+        assert_eq!(server.endpoint(), &endpoint);
+    }
+
+    #[test]
+    fn does_not_attach_to_other_services() {
+        let endpoint = serve(vec![("/version", b"It works!".to_vec())]);
+        assert!(attach(&endpoint).is_err());
+        let endpoint = serve(vec![("/tika", b"This is Tika Server".to_vec())]);
+        assert!(attach(&endpoint).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn drops_servers_within_a_runtime() {
+        let endpoint = serve(vec![("/version", b"Apache Tika 2.9.1".to_vec())]);
+        let server = attach(&endpoint).unwrap();
+        // the blocking http client must not be dropped on a worker of the runtime
+        let client = server.client();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let async_client = server.async_client();
+            drop(server);
+            drop(client);
+            drop(async_client);
+        });
+    }
+}
@@ -0,0 +1,100 @@
+use crate::error::Result;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// The main class of tika 1.x servers
+pub const LEGACY_MAIN_CLASS: &str = "org.apache.tika.server.TikaServerCli";
+
+/// The main class of tika 2.x and later servers
+pub const MAIN_CLASS: &str = "org.apache.tika.server.core.TikaServerCli";
+
+/// The server artifact of tika 2.x and later.
+/// Tika 1.x only ships the single `tika-server` artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServerArtifact {
+    /// `tika-server-standard`, the server with the standard parsers
+    #[default]
+    Standard,
+    /// `tika-server-full`, the server with additional parsers, like the scientific parsers
+    Full,
+}
+
+impl ServerArtifact {
+    /// the maven artifact id of the server for the tika `version`
+    pub fn artifact_id(&self, version: &str) -> &'static str {
+        if is_legacy(version) {
+            return "tika-server";
+        }
+        match self {
+            ServerArtifact::Standard => "tika-server-standard",
+            ServerArtifact::Full => "tika-server-full",
+        }
+    }
+}
+
+/// the major version of a tika `version`, like `2` for `2.9.2`
+pub fn major_version(version: &str) -> Option<u32> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// whether the tika `version` is a 1.x version.
+/// Unknown versions are treated as the current major version.
+pub fn is_legacy(version: &str) -> bool {
+    major_version(version) == Some(1)
+}
+
+/// the main class of the server for the tika `version`
+pub fn main_class(version: &str) -> &'static str {
+    if is_legacy(version) {
+        LEGACY_MAIN_CLASS
+    } else {
+        MAIN_CLASS
+    }
+}
+
+/// The `META-INF/MANIFEST.MF` of a tika server jar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarManifest {
+    /// the `Main-Class` of the jar
+    pub main_class: Option<String>,
+    /// the `Implementation-Version` of the jar, like `2.9.2`
+    pub version: Option<String>,
+}
+
+impl JarManifest {
+    /// Reads the manifest of the `jar`
+    pub fn read<P: AsRef<Path>>(jar: P) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(jar)?)?;
+        let mut manifest = String::new();
+        archive
+            .by_name("META-INF/MANIFEST.MF")?
+            .read_to_string(&mut manifest)?;
+        Ok(JarManifest::parse(&manifest))
+    }
+
+    /// Parses the content of a manifest, whose long values are continued on lines that start
+    /// with a single space
+    pub fn parse(manifest: &str) -> Self {
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for line in manifest.lines() {
+            if let Some(continued) = line.strip_prefix(' ') {
+                if let Some((_, value)) = entries.last_mut() {
+                    value.push_str(continued);
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                entries.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value.clone())
+        };
+        JarManifest {
+            main_class: get("Main-Class"),
+            version: get("Implementation-Version"),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::server::jar::major_version;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The java executable within a java home
+#[cfg(windows)]
+const JAVA_BIN: &str = "bin/java.exe";
+#[cfg(not(windows))]
+const JAVA_BIN: &str = "bin/java";
+
+/// the minimum java version the tika server of the tika `version` runs on,
+/// java 11 for tika 3.x and later, java 8 otherwise
+pub fn minimum_java_version(tika_version: &str) -> u32 {
+    match major_version(tika_version) {
+        Some(major) if major >= 3 => 11,
+        _ => 8,
+    }
+}
+
+/// A java runtime to launch tika server jars with
+///
+/// # Example
+///
+/// ```edition2018,no_run
+/// # fn run() -> rustika::Result<()> {
+/// use rustika::server::java::JavaRuntime;
+///
+/// let java = JavaRuntime::resolve(None)?;
+/// println!("{} is java {}", java.path().display(), java.version());
+/// java.check("2.9.2")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaRuntime {
+    path: PathBuf,
+    version: String,
+    major: u32,
+}
+
+impl JavaRuntime {
+    /// Finds the java runtime, which is the `explicit` one if set, else the one of the
+    /// `JAVA_HOME` env var and otherwise the `java` in `PATH`.
+    ///
+    /// The `explicit` path is either the java executable or a java home.
+    pub fn resolve(explicit: Option<&Path>) -> Result<Self> {
+        if let Some(path) = explicit {
+            return Self::at(path);
+        }
+        if let Some(home) = env::var_os("JAVA_HOME").filter(|home| !home.is_empty()) {
+            match Self::at(&home) {
+                Ok(java) => return Ok(java),
+                Err(e) => warn!("Ignoring JAVA_HOME: {}", e),
+            }
+        }
+        match which::which("java") {
+            Ok(path) => Self::at(path),
+            Err(_) => Err(ErrorKind::JavaNotFound {
+                msg: "java is neither in JAVA_HOME nor in PATH".to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// The java runtime at `path`, which is either the java executable or a java home.
+    /// Its version is determined by running `java -version`.
+    pub fn at<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let path = if path.is_dir() {
+            path.join(JAVA_BIN)
+        } else {
+            path.into()
+        };
+        let output =
+            Command::new(&path)
+                .arg("-version")
+                .output()
+                .map_err(|e| ErrorKind::JavaNotFound {
+                    msg: format!("failed to run {}: {}", path.display(), e),
+                })?;
+        // java prints its version to stderr
+        let printed = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        );
+        let (version, major) = parse_java_version(&printed).ok_or_else(|| {
+            Error::from(ErrorKind::JavaNotFound {
+                msg: format!(
+                    "{} printed no java version: {}",
+                    path.display(),
+                    printed.trim()
+                ),
+            })
+        })?;
+        Ok(JavaRuntime {
+            path,
+            version,
+            major,
+        })
+    }
+
+    /// the java executable
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the version `java -version` printed, like `17.0.9` or `1.8.0_392`
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// the feature release of the java version, like `17` or `8` for `1.8.0_392`
+    pub fn major_version(&self) -> u32 {
+        self.major
+    }
+
+    /// Fails with `ErrorKind::JavaVersion` if the runtime is older than the tika server of
+    /// the tika `version` requires
+    pub fn check(&self, tika_version: &str) -> Result<()> {
+        let required = minimum_java_version(tika_version);
+        if self.major < required {
+            return Err(ErrorKind::JavaVersion {
+                java: self.path.display().to_string(),
+                version: self.version.clone(),
+                tika_version: tika_version.to_string(),
+                required,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// the version and its feature release within the output of `java -version`, like
+/// `openjdk version "17.0.9" 2023-10-17` or `java version "1.8.0_392"`
+pub(crate) fn parse_java_version(output: &str) -> Option<(String, u32)> {
+    let line = output.lines().find(|line| line.contains(" version "))?;
+    let version = line.split('"').nth(1)?.trim();
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok()?,
+        major => major,
+    };
+    Some((version.to_string(), major))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(output: &str) -> Option<(String, u32)> {
+        parse_java_version(output)
+    }
+
+    #[test]
+    fn parses_legacy_versions() {
+        let output = "java version \"1.8.0_292\"\n\
+                      Java(TM) SE Runtime Environment (build 1.8.0_292-b10)\n\
+                      Java HotSpot(TM) 64-Bit Server VM (build 25.292-b10, mixed mode)\n";
+        assert_eq!(version(output), Some(("1.8.0_292".to_string(), 8)));
+    }
+
+    #[test]
+    fn parses_versions() {
+        let output = "openjdk version \"11.0.2\" 2019-01-15\n\
+                      OpenJDK Runtime Environment 18.9 (build 11.0.2+9)\n\
+                      OpenJDK 64-Bit Server VM 18.9 (build 11.0.2+9, mixed mode)\n";
+        assert_eq!(version(output), Some(("11.0.2".to_string(), 11)));
+    }
+
+    #[test]
+    fn parses_versions_without_minor_version() {
+        assert_eq!(
+            version("java version \"17\" 2021-09-14 LTS\n"),
+            Some(("17".to_string(), 17))
+        );
+        let output = "openjdk version \"21\" 2023-09-19\n\
+                      OpenJDK Runtime Environment (build 21+35-2513)\n\
+                      OpenJDK 64-Bit Server VM (build 21+35-2513, mixed mode, sharing)\n";
+        assert_eq!(version(output), Some(("21".to_string(), 21)));
+    }
+
+    #[test]
+    fn parses_early_access_versions() {
+        assert_eq!(
+            version("openjdk version \"23-ea\" 2024-09-17\n"),
+            Some(("23-ea".to_string(), 23))
+        );
+    }
+
+    #[test]
+    fn skips_other_output() {
+        let output = "Picked up JAVA_TOOL_OPTIONS: -Xmx1g\n\
+                      openjdk version \"17.0.9\" 2023-10-17\n";
+        assert_eq!(version(output), Some(("17.0.9".to_string(), 17)));
+    }
+
+    #[test]
+    fn rejects_unknown_output() {
+        assert_eq!(version(""), None);
+        assert_eq!(version("command not found: java\n"), None);
+        assert_eq!(version("openjdk version 17\n"), None);
+        assert_eq!(version("openjdk version \"\"\n"), None);
+        assert_eq!(version("java version \"1\"\n"), None);
+    }
+}
@@ -0,0 +1,270 @@
+use log::Level;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// The `log` target the lines of a spawned tika server are logged to
+pub const LOG_TARGET: &str = "rustika::server";
+
+/// How many of the last lines of a spawned tika server are kept by default
+pub const DEFAULT_LOG_LINES: usize = 1000;
+
+/// The name of the log file of a spawned tika server, within the `logs` dir of the `tika_path`
+pub const LOG_FILE: &str = "tika-server.log";
+
+/// How the log files of a spawned tika server are rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    /// the size at which the log file is rotated
+    pub max_bytes: u64,
+    /// how many rotated log files, `tika-server.log.1` and so on, are kept
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        LogRotation {
+            max_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+/// The level of a line logged by the tika server, like `INFO  Started Apache Tika server`
+/// or `WARN  [main] 10:21:42,001 ...`
+pub(crate) fn log_level(line: &str) -> Option<Level> {
+    line.split_whitespace()
+        .take(3)
+        .find_map(|token| match token.trim_end_matches(':') {
+            "FATAL" | "ERROR" | "SEVERE" => Some(Level::Error),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "INFO" => Some(Level::Info),
+            "DEBUG" | "FINE" => Some(Level::Debug),
+            "TRACE" | "FINER" | "FINEST" => Some(Level::Trace),
+            _ => None,
+        })
+}
+
+/// The last lines a spawned tika server logged
+#[derive(Debug, Clone)]
+pub(crate) struct ServerLogs {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl ServerLogs {
+    pub(crate) fn new(capacity: usize) -> Self {
+        ServerLogs {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity.min(1024)))),
+            capacity,
+        }
+    }
+
+    fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// the kept lines, oldest first
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+
+    /// the last `n` kept lines
+    pub(crate) fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Drains the output of a spawned tika server into the `log` crate, the `ServerLogs` and
+/// optionally rotating log files
+#[derive(Debug, Clone)]
+pub(crate) struct LogPump {
+    logs: ServerLogs,
+    /// whether the lines are printed to `std::out` as well
+    verbose: bool,
+    file: Option<Arc<Mutex<RotatingLog>>>,
+}
+
+impl LogPump {
+    /// A pump into the `logs`, that writes log files into `dir`, if `rotation` is set
+    pub(crate) fn new(
+        logs: ServerLogs,
+        verbose: bool,
+        dir: &Path,
+        rotation: Option<LogRotation>,
+    ) -> io::Result<Self> {
+        let file = match rotation {
+            Some(rotation) => Some(Arc::new(Mutex::new(RotatingLog::open(dir, rotation)?))),
+            None => None,
+        };
+        Ok(LogPump {
+            logs,
+            verbose,
+            file,
+        })
+    }
+
+    /// Reads the lines of the `output` of the server on a background thread, until the
+    /// server closes it
+    pub(crate) fn drain<R: Read + Send + 'static>(&self, output: R) -> JoinHandle<()> {
+        let pump = self.clone();
+        thread::spawn(move || {
+            // lines without level, like stack traces, continue the previous line
+            let mut level = Level::Info;
+            let mut reader = BufReader::new(output);
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match reader.read_until(b'\n', &mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        warn!("Failed to read the output of the tika server: {}", e);
+                        break;
+                    }
+                }
+                // the JVM doesn't necessarily log UTF-8, which must not stop the draining
+                let line = String::from_utf8_lossy(&buf)
+                    .trim_end_matches(&['\r', '\n'][..])
+                    .to_string();
+                level = log_level(&line).unwrap_or(level);
+                pump.write(level, line);
+            }
+        })
+    }
+
+    fn write(&self, level: Level, line: String) {
+        log!(target: LOG_TARGET, level, "{}", line);
+        if self.verbose {
+            println!("{}", line);
+        }
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = file.write_line(&line) {
+                warn!("Failed to write tika server log file: {}", e);
+            }
+        }
+        self.logs.push(line);
+    }
+}
+
+/// A log file that is rotated once it exceeds its max size
+#[derive(Debug)]
+struct RotatingLog {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+    rotation: LogRotation,
+}
+
+impl RotatingLog {
+    fn open(dir: &Path, rotation: LogRotation) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(RotatingLog {
+            path,
+            file,
+            len,
+            rotation,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let size = line.len() as u64 + 1;
+        if self.len > 0 && self.len + size > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.len += size;
+        Ok(())
+    }
+
+    /// moves `tika-server.log.{n}` to `tika-server.log.{n+1}` and starts a new log file
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.rotation.max_files > 0 {
+            for n in (1..self.rotation.max_files).rev() {
+                let from = rotated(n);
+                if from.exists() {
+                    fs::rename(&from, rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_keeps_reading_after_invalid_utf8() {
+        let logs = ServerLogs::new(10);
+        let pump = LogPump::new(logs.clone(), false, Path::new("."), None).unwrap();
+        let output: &[u8] = b"INFO  starting\n\xff\xfe broken\r\nWARN  still here\nlast";
+        pump.drain(output).join().unwrap();
+        assert_eq!(
+            logs.lines(),
+            vec![
+                "INFO  starting",
+                "\u{fffd}\u{fffd} broken",
+                "WARN  still here",
+                "last"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_last_lines() {
+        let logs = ServerLogs::new(2);
+        for line in &["a", "b", "c"] {
+            logs.push(line.to_string());
+        }
+        assert_eq!(logs.lines(), vec!["b", "c"]);
+        assert_eq!(logs.tail(1), vec!["c"]);
+    }
+
+    #[test]
+    fn parses_log_levels() {
+        assert_eq!(
+            log_level("INFO  Started Apache Tika server"),
+            Some(Level::Info)
+        );
+        assert_eq!(
+            log_level("WARN  [main] 10:21:42,001 oops"),
+            Some(Level::Warn)
+        );
+        assert_eq!(log_level("SEVERE: failed"), Some(Level::Error));
+        assert_eq!(log_level("\tat org.apache.tika.Foo"), None);
+    }
+}
@@ -1 +1,190 @@
+use reqwest::blocking;
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
 
+pub mod cache;
+pub mod config;
+pub mod discovery;
+pub mod download;
+pub mod handle;
+pub mod jar;
+pub mod java;
+pub mod logs;
+pub mod options;
+pub(crate) mod port;
+
+pub use self::handle::TikaServer;
+
+/// How long a health check waits for the tika server to respond
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a spawned tika server may take until it serves requests by default
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a spawned tika server may take to shut down gracefully by default
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a shutting down tika server is checked for whether it exited
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many of the last lines a spawned tika server logged are included in startup errors
+pub(crate) const LOG_TAIL_LINES: usize = 50;
+
+/// The health of a tika server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerHealth {
+    /// whether the tika server responded
+    pub reachable: bool,
+    /// how long the tika server took to respond
+    pub latency: Option<Duration>,
+    /// the version the tika server reported, like `Apache Tika 1.20`
+    pub version: Option<String>,
+    /// whether the spawned server process is still running, `None` if the client has no
+    /// spawned server, because it attached to the server or the server was shut down
+    pub process_alive: Option<bool>,
+}
+
+impl ServerHealth {
+    /// whether the tika server is reachable and its process, if spawned, is running
+    pub fn is_live(&self) -> bool {
+        self.reachable && self.process_alive != Some(false)
+    }
+}
+
+/// The process of a spawned tika server
+#[derive(Debug, Default)]
+pub(crate) struct ServerProcess(Mutex<Option<Child>>);
+
+impl ServerProcess {
+    /// the spawned process
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Option<Child>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// the spawned process, without locking
+    pub(crate) fn get_mut(&mut self) -> &mut Option<Child> {
+        self.0.get_mut().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// whether the spawned process is still running, `None` if no process was spawned
+    pub(crate) fn is_alive(&self) -> Option<bool> {
+        self.lock()
+            .as_mut()
+            .map(|child| matches!(child.try_wait(), Ok(None)))
+    }
+}
+
+/// the version of a `/version` response, `None` if it is empty
+pub(crate) fn parse_version(body: &str) -> Option<String> {
+    Some(body.trim().to_string()).filter(|version| !version.is_empty())
+}
+
+/// whether the body of a `/version` response comes from a tika server, like `Apache Tika 2.9.1`
+pub(crate) fn is_tika_version(body: &str) -> bool {
+    body.trim_start().starts_with("Apache Tika")
+}
+
+/// the version the server at `endpoint` reports, `None` if no tika server answers within the
+/// `HEALTH_CHECK_TIMEOUT`
+pub(crate) fn tika_version(client: &blocking::Client, endpoint: &Url) -> Option<String> {
+    let body = client
+        .get(endpoint.join("version").ok()?)
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .text()
+        .ok()?;
+    Some(body)
+        .filter(|body| is_tika_version(body))
+        .and_then(|body| parse_version(&body))
+}
+
+/// Shuts the spawned server `child` down and reaps it.
+///
+/// On unix the server runs as leader of its own process group, so `SIGTERM` is sent to the
+/// whole group, including JVMs started by launcher scripts like `tika-rest-server`. If the
+/// group didn't exit within the `timeout`, it is killed with `SIGKILL`.
+/// Elsewhere the `child` is killed right away.
+pub(crate) fn shutdown(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+    terminate(child)?;
+    let start = Instant::now();
+    let mut status = None;
+    loop {
+        if status.is_none() {
+            status = child.try_wait()?;
+        }
+        if status.is_some() && !group_alive(child) {
+            break;
+        }
+        if start.elapsed() >= timeout {
+            if !timeout.is_zero() {
+                warn!(
+                    "Tika server did not shut down within {:?}, killing it",
+                    timeout
+                );
+            }
+            kill(child)?;
+            break;
+        }
+        thread::sleep(SHUTDOWN_POLL_INTERVAL);
+    }
+    match status {
+        Some(status) => Ok(status),
+        None => child.wait(),
+    }
+}
+
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    signal_group(child, libc::SIGTERM)
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    signal_group(child, libc::SIGKILL)
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+/// whether a process of the group of the `child` is still running
+#[cfg(unix)]
+fn group_alive(child: &Child) -> bool {
+    // signal 0 only checks whether the group still exists
+    // SAFETY: `kill` has no memory safety requirements
+    let alive = unsafe { libc::kill(-(child.id() as libc::pid_t), 0) } == 0;
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn group_alive(_: &Child) -> bool {
+    false
+}
+
+/// sends the `signal` to the process group the `child` leads, a group that is gone is ignored
+#[cfg(unix)]
+fn signal_group(child: &Child, signal: libc::c_int) -> io::Result<()> {
+    // SAFETY: `kill` has no memory safety requirements
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), signal) } == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::ESRCH) {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
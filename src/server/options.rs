@@ -0,0 +1,419 @@
+use crate::error::{Error, Result};
+use crate::server::config::ServerConfig;
+use crate::server::jar::is_legacy;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The options a tika server is launched with
+///
+/// Tika 1.x takes all options on the command line. Tika 2.x and later spawns a child process
+/// by default and reads the JVM flags of the child process, `maxFiles`, the timeouts,
+/// `enableUnsecureFeatures` and `cors` from the `<server>` params of the `tika-config.xml`.
+/// For these versions, the options are written into a generated `tika-config.xml`, together
+/// with the `ServerConfig` of the builder, if any, which can't be combined with a `config`
+/// file. `enable_file_url` is only supported by tika 1.x and requires `enable_unsecure_features`.
+///
+/// # Example
+///
+/// Give the process that parses documents enough memory for big PDFs
+///
+/// ```edition2018
+/// # use rustika::TikaBuilder;
+/// use rustika::server::options::ServerOptions;
+/// use std::time::Duration;
+///
+/// let options = ServerOptions::default()
+///     .spawn_child(true)
+///     .max_heap("4g")
+///     .task_timeout(Duration::from_secs(300));
+/// let builder = TikaBuilder::default().server_options(options);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerOptions {
+    /// the max heap of the process that parses documents, like `4g`.
+    /// This is the child process, if one is spawned, and the server process otherwise.
+    pub max_heap: Option<String>,
+    /// additional flags of the JVM of the server process, like `-Dfile.encoding=UTF-8`
+    pub jvm_args: Vec<String>,
+    /// additional flags of the JVM of the child process, passed as `-J` flags to tika 1.x and
+    /// as `forkedJvmArgs` to tika 2.x and later
+    pub child_jvm_args: Vec<String>,
+    /// whether the server parses in a child process, that is restarted if it crashes.
+    /// `None` keeps the default of the tika version, which is `false` for tika 1.x.
+    pub spawn_child: Option<bool>,
+    /// after how many files the child process is restarted
+    pub max_files: Option<u64>,
+    /// after how long a task of the child process is canceled
+    pub task_timeout: Option<Duration>,
+    /// after how long without a ping the child process is restarted
+    pub ping_timeout: Option<Duration>,
+    /// the `tika-config.xml` of the server
+    pub config: Option<PathBuf>,
+    /// whether endpoints like `/unpack` of remote files are enabled
+    pub enable_unsecure_features: bool,
+    /// whether documents can be fetched from `fileUrl` headers, requires
+    /// `enable_unsecure_features`
+    pub enable_file_url: bool,
+    /// the origins allowed to make cross origin requests, like `*`
+    pub cors: Option<String>,
+    /// arguments appended to the command line of the server
+    pub extra_args: Vec<String>,
+}
+
+impl ServerOptions {
+    /// The max heap of the process that parses documents, like `4g`
+    pub fn max_heap<T: Into<String>>(mut self, heap: T) -> Self {
+        self.max_heap = Some(heap.into());
+        self
+    }
+
+    /// Adds a flag of the JVM of the server process, like `-Dfile.encoding=UTF-8`
+    pub fn jvm_arg<T: Into<String>>(mut self, arg: T) -> Self {
+        self.jvm_args.push(arg.into());
+        self
+    }
+
+    /// Adds a flag of the JVM of the child process, like `-XX:+UseG1GC`
+    pub fn child_jvm_arg<T: Into<String>>(mut self, arg: T) -> Self {
+        self.child_jvm_args.push(arg.into());
+        self
+    }
+
+    /// Whether the server parses in a child process
+    pub fn spawn_child(mut self, spawn_child: bool) -> Self {
+        self.spawn_child = Some(spawn_child);
+        self
+    }
+
+    /// After how many files the child process is restarted
+    pub fn max_files(mut self, max_files: u64) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+
+    /// After how long a task of the child process is canceled
+    pub fn task_timeout(mut self, timeout: Duration) -> Self {
+        self.task_timeout = Some(timeout);
+        self
+    }
+
+    /// After how long without a ping the child process is restarted
+    pub fn ping_timeout(mut self, timeout: Duration) -> Self {
+        self.ping_timeout = Some(timeout);
+        self
+    }
+
+    /// The `tika-config.xml` of the server
+    pub fn config<P: AsRef<Path>>(mut self, config: P) -> Self {
+        self.config = Some(config.as_ref().into());
+        self
+    }
+
+    /// Enables endpoints that access remote files
+    pub fn enable_unsecure_features(mut self) -> Self {
+        self.enable_unsecure_features = true;
+        self
+    }
+
+    /// Enables fetching documents from `fileUrl` headers, requires `enable_unsecure_features`
+    pub fn enable_file_url(mut self) -> Self {
+        self.enable_file_url = true;
+        self
+    }
+
+    /// The origins allowed to make cross origin requests, like `*`
+    pub fn cors<T: Into<String>>(mut self, origins: T) -> Self {
+        self.cors = Some(origins.into());
+        self
+    }
+
+    /// Appends an argument to the command line of the server
+    pub fn arg<T: Into<String>>(mut self, arg: T) -> Self {
+        self.extra_args.push(arg.into());
+        self
+    }
+
+    /// Renders the options for a server of the tika `version`.
+    ///
+    /// The options tika 2.x and later reads from the `tika-config.xml` are added to a
+    /// generated config, which is based on the `config` of the builder, if any.
+    pub(crate) fn render(
+        &self,
+        version: &str,
+        config: Option<&ServerConfig>,
+    ) -> Result<ServerArgs> {
+        if self.enable_file_url && !self.enable_unsecure_features {
+            return Err(Error::config(
+                "enable_file_url requires enable_unsecure_features, the server refuses to \
+                 start otherwise",
+            ));
+        }
+        let legacy = is_legacy(version);
+        let forks = if legacy {
+            self.spawn_child == Some(true)
+        } else {
+            self.spawn_child != Some(false)
+        };
+        let mut rendered = ServerArgs {
+            jvm_args: self.jvm_args.clone(),
+            args: Vec::new(),
+            config: config.cloned(),
+        };
+
+        if legacy && forks {
+            rendered.args.push("-spawnChild".to_string());
+        } else if !legacy && !forks {
+            rendered.args.push("-noFork".to_string());
+        }
+
+        if !forks && !self.child_jvm_args.is_empty() {
+            return Err(Error::config(
+                "JVM args of the child process require the server to spawn a child",
+            ));
+        }
+        let heap = self.max_heap.as_ref().map(|heap| format!("-Xmx{}", heap));
+        if forks {
+            for arg in heap.iter().chain(&self.child_jvm_args) {
+                let arg = arg.trim_start_matches('-');
+                if legacy {
+                    rendered.args.push(format!("-J{}", arg));
+                } else {
+                    rendered
+                        .config_mut()
+                        .forked_jvm_args
+                        .push(format!("-{}", arg));
+                }
+            }
+        } else {
+            rendered.jvm_args.extend(heap);
+        }
+
+        let mut params = vec![
+            ("maxFiles", self.max_files.map(|n| n.to_string())),
+            (
+                "taskTimeoutMillis",
+                self.task_timeout.map(|t| t.as_millis().to_string()),
+            ),
+            (
+                "pingTimeoutMillis",
+                self.ping_timeout.map(|t| t.as_millis().to_string()),
+            ),
+            (
+                "enableUnsecureFeatures",
+                Some(String::new()).filter(|_| self.enable_unsecure_features),
+            ),
+            (
+                "enableFileUrl",
+                Some(String::new()).filter(|_| self.enable_file_url),
+            ),
+            ("cors", self.cors.clone()),
+        ];
+        params.retain(|(_, value)| value.is_some());
+        for (name, value) in params {
+            let value = value.unwrap_or_default();
+            if legacy {
+                // only `cors` has two dashes
+                let dashes = if name == "cors" { "--" } else { "-" };
+                rendered.args.push(format!("{}{}", dashes, name));
+                if !value.is_empty() {
+                    rendered.args.push(value);
+                }
+                continue;
+            }
+            if name == "enableFileUrl" {
+                return Err(Error::config(format!(
+                    "tika {} does not support enableFileUrl, which was removed in tika 2.0",
+                    version
+                )));
+            }
+            let config = rendered.config_mut();
+            if config.has_server_param(name) {
+                return Err(Error::config(format!(
+                    "The server param {} is set by both the ServerOptions and the ServerConfig",
+                    name
+                )));
+            }
+            // flags without value are enabled
+            let value = if value.is_empty() {
+                "true".to_string()
+            } else {
+                value
+            };
+            config.server_params.push((name.to_string(), value));
+        }
+
+        if let Some(file) = &self.config {
+            if rendered.config.is_some() {
+                return Err(Error::config(format!(
+                    "The server is configured with both a generated tika-config.xml and {}",
+                    file.display()
+                )));
+            }
+            rendered.args.push("--config".to_string());
+            rendered.args.push(file.display().to_string());
+        }
+
+        rendered.args.extend(self.extra_args.iter().cloned());
+        Ok(rendered)
+    }
+}
+
+/// The rendered `ServerOptions`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ServerArgs {
+    /// the flags of the JVM of the server process
+    pub(crate) jvm_args: Vec<String>,
+    /// the arguments of the server
+    pub(crate) args: Vec<String>,
+    /// the `tika-config.xml` to generate for the server
+    pub(crate) config: Option<ServerConfig>,
+}
+
+impl ServerArgs {
+    /// the generated config, which is created if there is none yet
+    fn config_mut(&mut self) -> &mut ServerConfig {
+        self.config.get_or_insert_with(ServerConfig::default)
+    }
+
+    /// Writes the generated `tika-config.xml` into the `tika_path`, if any, and points the
+    /// server to it
+    pub(crate) fn write_config(&mut self, tika_path: &Path) -> Result<()> {
+        if let Some(config) = self.config.take() {
+            let file = config.write(tika_path)?;
+            self.args.push("--config".to_string());
+            self.args.push(file.display().to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn options() -> ServerOptions {
+        ServerOptions::default()
+            .spawn_child(true)
+            .max_heap("4g")
+            .child_jvm_arg("-XX:+UseG1GC")
+            .max_files(100)
+            .task_timeout(Duration::from_secs(300))
+            .cors("*")
+    }
+
+    #[test]
+    fn renders_legacy_options_as_flags() {
+        let rendered = options()
+            .jvm_arg("-Dfile.encoding=UTF-8")
+            .enable_unsecure_features()
+            .config("tika-config.xml")
+            .arg("-enableFileUrl")
+            .render("1.28.5", None)
+            .unwrap();
+        assert_eq!(rendered.jvm_args, strings(&["-Dfile.encoding=UTF-8"]));
+        assert_eq!(
+            rendered.args,
+            strings(&[
+                "-spawnChild",
+                "-JXmx4g",
+                "-JXX:+UseG1GC",
+                "-maxFiles",
+                "100",
+                "-taskTimeoutMillis",
+                "300000",
+                "-enableUnsecureFeatures",
+                "--cors",
+                "*",
+                "--config",
+                "tika-config.xml",
+                "-enableFileUrl",
+            ])
+        );
+        assert_eq!(rendered.config, None);
+    }
+
+    #[test]
+    fn renders_legacy_heap_of_server_without_child() {
+        let rendered = ServerOptions::default()
+            .max_heap("1g")
+            .render("1.20", None)
+            .unwrap();
+        assert_eq!(rendered.jvm_args, strings(&["-Xmx1g"]));
+        assert!(rendered.args.is_empty());
+        assert!(ServerOptions::default()
+            .child_jvm_arg("-Xss4m")
+            .render("1.20", None)
+            .is_err());
+    }
+
+    #[test]
+    fn writes_options_of_forked_process_into_config() {
+        let rendered = options().render("2.9.2", None).unwrap();
+        assert!(rendered.jvm_args.is_empty());
+        assert!(rendered.args.is_empty());
+        let expected = ServerConfig::default()
+            .server_param("maxFiles", 100)
+            .server_param("taskTimeoutMillis", 300000)
+            .server_param("cors", "*")
+            .forked_jvm_arg("-Xmx4g")
+            .forked_jvm_arg("-XX:+UseG1GC");
+        assert_eq!(rendered.config, Some(expected));
+    }
+
+    #[test]
+    fn merges_options_into_server_config() {
+        let config = ServerConfig::default()
+            .exclude_parser("a.Exe")
+            .server_param("pingTimeoutMillis", 1000);
+        let rendered = ServerOptions::default()
+            .max_heap("2g")
+            .enable_unsecure_features()
+            .render("3.0.0", Some(&config))
+            .unwrap();
+        let expected = config
+            .clone()
+            .server_param("enableUnsecureFeatures", true)
+            .forked_jvm_arg("-Xmx2g");
+        assert_eq!(rendered.config, Some(expected));
+
+        let duplicate = ServerOptions::default().ping_timeout(Duration::from_secs(1));
+        assert!(duplicate.render("2.9.2", Some(&config)).is_err());
+    }
+
+    #[test]
+    fn renders_options_of_server_without_fork() {
+        let rendered = ServerOptions::default()
+            .spawn_child(false)
+            .max_heap("2g")
+            .config("/etc/tika-config.xml")
+            .render("2.9.2", None)
+            .unwrap();
+        assert_eq!(rendered.jvm_args, strings(&["-Xmx2g"]));
+        assert_eq!(
+            rendered.args,
+            strings(&["-noFork", "--config", "/etc/tika-config.xml"])
+        );
+        assert_eq!(rendered.config, None);
+    }
+
+    #[test]
+    fn rejects_unsupported_combinations() {
+        let file_url = ServerOptions::default().enable_file_url();
+        let err = file_url.render("1.28", None).unwrap_err();
+        assert!(err.to_string().contains("enable_unsecure_features"));
+        let file_url = file_url.enable_unsecure_features();
+        assert!(file_url.render("2.9.2", None).is_err());
+        assert!(file_url.render("1.28", None).is_ok());
+
+        let both = ServerOptions::default().config("tika-config.xml");
+        assert!(both
+            .render("2.9.2", Some(&ServerConfig::default()))
+            .is_err());
+        assert!(both.clone().max_heap("1g").render("2.9.2", None).is_err());
+        assert!(both.max_heap("1g").render("1.28", None).is_ok());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::server;
+use reqwest::blocking;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use url::Url;
+
+/// How often a server is spawned on another free port, if the port it was given was taken
+/// before the server could bind it
+pub(crate) const PORT_ATTEMPTS: usize = 3;
+
+/// a free port on the `ip`, found by binding to port 0
+pub(crate) fn free_port(ip: IpAddr) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(SocketAddr::new(ip, 0))?;
+    Ok(listener.local_addr()?)
+}
+
+/// Fails with `ErrorKind::AddrInUse` if something is already listening on the `addr`
+pub(crate) fn check_available(addr: SocketAddr) -> Result<()> {
+    match TcpListener::bind(addr) {
+        Ok(_) => Ok(()),
+        Err(ref e) if e.kind() == io::ErrorKind::AddrInUse => {
+            let msg = match tika_version(addr) {
+                Some(version) => format!(
+                    "{} is already running there, use `SpawnMode::AttachOrSpawn` to attach to it",
+                    version
+                ),
+                None => "a process that isn't a tika server listens there, use port 0 instead"
+                    .to_string(),
+            };
+            Err(ErrorKind::AddrInUse { addr, msg }.into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether the spawned server failed to start, because something else bound its `addr` first
+pub(crate) fn lost_port(error: &Error, addr: SocketAddr) -> bool {
+    match error.kind() {
+        ErrorKind::Startup { stderr, .. } => {
+            stderr.contains("Address already in use")
+                || stderr.contains("BindException")
+                || check_available(addr).is_err()
+        }
+        _ => false,
+    }
+}
+
+/// the version a tika server at `addr` reports, `None` if no tika server answers
+fn tika_version(addr: SocketAddr) -> Option<String> {
+    let endpoint = Url::parse(&format!("http://{}/", addr)).ok()?;
+    server::tika_version(&blocking::Client::new(), &endpoint)
+}
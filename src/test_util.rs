@@ -0,0 +1,104 @@
+//! Fixtures shared by the unit tests
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+/// an empty dir in the temp dir, unique for the `name` and this process
+pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("rustika-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// writes the `content` to the relative `path` within the `dir`, creating its parent dirs
+pub(crate) fn write_file<C: AsRef<[u8]>>(dir: &Path, path: &str, content: C) -> PathBuf {
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// Serves the `files` by their path, like `/version`, and `404 Not Found` for everything
+/// else on a local port.
+/// Returns the url of the server, like `http://127.0.0.1:1234/`.
+pub(crate) fn serve(files: Vec<(&'static str, Vec<u8>)>) -> Url {
+    let files: HashMap<_, _> = files.into_iter().collect();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            if reader.read_line(&mut request).is_err() {
+                continue;
+            }
+            // skip the headers
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let mut stream = reader.into_inner();
+            let _ = match files.get(path) {
+                Some(body) => write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .and_then(|_| stream.write_all(body)),
+                None => stream.write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                ),
+            };
+        }
+    });
+    Url::parse(&format!("http://{}/", addr)).unwrap()
+}
+
+/// a local repository in the scratch dir `name` with the `files`, returns its dir and
+/// `file://` url
+pub(crate) fn repository(name: &str, files: Vec<(&str, Vec<u8>)>) -> (PathBuf, Url) {
+    let dir = scratch_dir(name);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        write_file(&dir, file, content);
+    }
+    let url = Url::from_directory_path(&dir).unwrap();
+    (dir, url)
+}
+
+/// tells the re-executed test binary how long the fake server lives, in milliseconds
+const FAKE_SERVER_MILLIS: &str = "RUSTIKA_FAKE_SERVER_MILLIS";
+
+/// Spawns a process that stands in for a spawned server and exits after the `lifetime`.
+/// The process is this test binary, that only runs `fake_server_process`, so it works on
+/// every platform.
+pub(crate) fn fake_server(lifetime: Duration) -> Child {
+    Command::new(env::current_exe().unwrap())
+        .args(["test_util::fake_server_process", "--exact", "--ignored"])
+        .env(FAKE_SERVER_MILLIS, lifetime.as_millis().to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+/// the process of `fake_server`, ignored unless the test binary is re-executed by it
+#[test]
+#[ignore]
+fn fake_server_process() {
+    if let Some(millis) = env::var(FAKE_SERVER_MILLIS)
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+    {
+        thread::sleep(Duration::from_millis(millis));
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+/// The mime type of the document
+pub const CONTENT_TYPE: &str = "Content-Type";
+/// The size of the document in bytes
+pub const CONTENT_LENGTH: &str = "Content-Length";
+/// The title of the document
+pub const TITLE: &str = "dc:title";
+/// The creators of the document
+pub const CREATOR: &str = "dc:creator";
+/// When the document was created
+pub const CREATED: &str = "dcterms:created";
+/// When the document was last modified
+pub const MODIFIED: &str = "dcterms:modified";
+/// The language of the document
+pub const LANGUAGE: &str = "dc:language";
+/// The number of pages of paged documents like pdfs
+pub const PAGE_COUNT: &str = "xmpTPg:NPages";
+
+/// A value of a metadata key.
+/// The tika server returns keys with multiple values as json arrays.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetadataValue {
+    /// a key with a single value
+    Single(String),
+    /// a key with multiple values
+    Multi(Vec<String>),
+}
+
+impl MetadataValue {
+    /// the first value, for `MetadataValue::Single` this is the value itself
+    pub fn first(&self) -> Option<&str> {
+        match self {
+            MetadataValue::Single(value) => Some(value.as_str()),
+            MetadataValue::Multi(values) => values.first().map(String::as_str),
+        }
+    }
+
+    /// all values of this key
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            MetadataValue::Single(value) => vec![value.as_str()],
+            MetadataValue::Multi(values) => values.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// whether this key contains multiple values
+    pub fn is_multi(&self) -> bool {
+        match self {
+            MetadataValue::Single(_) => false,
+            MetadataValue::Multi(_) => true,
+        }
+    }
+}
+
+/// The metadata of a document as returned by the `/meta` endpoint of the tika server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata(pub HashMap<String, MetadataValue>);
+
+impl Metadata {
+    /// the value of the `key`
+    pub fn get<T: AsRef<str>>(&self, key: T) -> Option<&MetadataValue> {
+        self.0.get(key.as_ref())
+    }
+
+    /// the first value of the `key`
+    pub fn get_first<T: AsRef<str>>(&self, key: T) -> Option<&str> {
+        self.get(key).and_then(MetadataValue::first)
+    }
+
+    /// all values of the `key`, empty if the key is not present
+    pub fn get_all<T: AsRef<str>>(&self, key: T) -> Vec<&str> {
+        self.get(key).map(MetadataValue::values).unwrap_or_default()
+    }
+
+    /// whether the `key` is present
+    pub fn contains_key<T: AsRef<str>>(&self, key: T) -> bool {
+        self.0.contains_key(key.as_ref())
+    }
+
+    /// all keys of the metadata
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// iterates over all key value pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetadataValue)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// the number of keys
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// whether no keys are present
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The mime type of the document (`Content-Type`), e.g. `application/pdf`
+    pub fn content_type(&self) -> Option<&str> {
+        self.get_first(CONTENT_TYPE)
+    }
+
+    /// The size of the document in bytes (`Content-Length`)
+    pub fn content_length(&self) -> Option<u64> {
+        self.get_first(CONTENT_LENGTH)
+            .and_then(|len| len.trim().parse().ok())
+    }
+
+    /// The title of the document (`dc:title`)
+    pub fn title(&self) -> Option<&str> {
+        self.get_first(TITLE)
+    }
+
+    /// All creators of the document (`dc:creator`)
+    pub fn creators(&self) -> Vec<&str> {
+        self.get_all(CREATOR)
+    }
+
+    /// The ISO 8601 timestamp of when the document was created (`dcterms:created`)
+    pub fn created(&self) -> Option<&str> {
+        self.get_first(CREATED)
+    }
+
+    /// The ISO 8601 timestamp of when the document was last modified (`dcterms:modified`)
+    pub fn modified(&self) -> Option<&str> {
+        self.get_first(MODIFIED)
+    }
+
+    /// The language of the document (`dc:language`)
+    pub fn language(&self) -> Option<&str> {
+        self.get_first(LANGUAGE)
+    }
+
+    /// The number of pages of the document (`xmpTPg:NPages`)
+    pub fn page_count(&self) -> Option<u32> {
+        self.get_first(PAGE_COUNT)
+            .and_then(|pages| pages.trim().parse().ok())
+    }
+}
+
+impl From<HashMap<String, MetadataValue>> for Metadata {
+    fn from(map: HashMap<String, MetadataValue>) -> Self {
+        Metadata(map)
+    }
+}
+
+impl IntoIterator for Metadata {
+    type Item = (String, MetadataValue);
+    type IntoIter = std::collections::hash_map::IntoIter<String, MetadataValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -1,4 +1,10 @@
 pub mod config;
 pub mod detector;
+pub mod metadata;
+pub mod options;
+pub mod recursive;
+pub(crate) mod request;
 pub mod response;
+pub mod structured;
 pub mod translate;
+pub mod unpack;
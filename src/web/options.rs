@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+/// How the tika server applies OCR to pdfs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfOcrStrategy {
+    /// never apply OCR
+    NoOcr,
+    /// only apply OCR and ignore the text of the pdf
+    OcrOnly,
+    /// apply OCR and extract the text of the pdf
+    OcrAndText,
+    /// apply OCR only to pages with little or no text
+    Auto,
+}
+
+impl PdfOcrStrategy {
+    /// the name of the strategy the tika server expects
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PdfOcrStrategy::NoOcr => "no_ocr",
+            PdfOcrStrategy::OcrOnly => "ocr_only",
+            PdfOcrStrategy::OcrAndText => "ocr_and_text",
+            PdfOcrStrategy::Auto => "auto",
+        }
+    }
+}
+
+/// Options that configure how the tika server parses a single document.
+/// The options are sent as `X-Tika-*` request headers.
+///
+/// # Example
+///
+/// Apply german OCR to all pages of a pdf
+///
+/// ```edition2018
+/// use rustika::web::options::{ParseOptions, PdfOcrStrategy};
+///
+/// let options = ParseOptions::default()
+///     .ocr_language("deu")
+///     .pdf_ocr_strategy(PdfOcrStrategy::OcrOnly)
+///     .timeout(std::time::Duration::from_secs(120));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// the tesseract language(s) used for OCR, e.g. `eng+deu`
+    pub ocr_language: Option<String>,
+    /// whether OCR should be skipped entirely
+    pub skip_ocr: Option<bool>,
+    /// how OCR is applied to pdfs
+    pub pdf_ocr_strategy: Option<PdfOcrStrategy>,
+    /// whether the images of pdfs should be extracted as embedded documents
+    pub pdf_extract_inline_images: Option<bool>,
+    /// whether the text of pdfs should be sorted by its position on the page
+    pub pdf_sort_by_position: Option<bool>,
+    /// whether the text of annotations of pdfs should be extracted
+    pub pdf_extract_annotation_text: Option<bool>,
+    /// the password to decrypt the document
+    pub password: Option<String>,
+    /// how long the tika server may take to parse the document
+    pub timeout: Option<Duration>,
+    /// any additional request headers
+    pub headers: Vec<(String, String)>,
+}
+
+impl ParseOptions {
+    /// The tesseract language(s) to use for OCR, e.g. `eng` or `eng+deu`
+    pub fn ocr_language<T: Into<String>>(mut self, language: T) -> Self {
+        self.ocr_language = Some(language.into());
+        self
+    }
+
+    /// Whether OCR should be skipped entirely
+    pub fn skip_ocr(mut self, skip: bool) -> Self {
+        self.skip_ocr = Some(skip);
+        self
+    }
+
+    /// How OCR is applied to pdfs
+    pub fn pdf_ocr_strategy(mut self, strategy: PdfOcrStrategy) -> Self {
+        self.pdf_ocr_strategy = Some(strategy);
+        self
+    }
+
+    /// Whether the images of pdfs should be extracted as embedded documents.
+    /// This is required to `unpack` the images of a pdf.
+    pub fn pdf_extract_inline_images(mut self, extract: bool) -> Self {
+        self.pdf_extract_inline_images = Some(extract);
+        self
+    }
+
+    /// Whether the text of pdfs should be sorted by its position on the page
+    pub fn pdf_sort_by_position(mut self, sort: bool) -> Self {
+        self.pdf_sort_by_position = Some(sort);
+        self
+    }
+
+    /// Whether the text of annotations of pdfs should be extracted
+    pub fn pdf_extract_annotation_text(mut self, extract: bool) -> Self {
+        self.pdf_extract_annotation_text = Some(extract);
+        self
+    }
+
+    /// The password to decrypt the document
+    pub fn password<T: Into<String>>(mut self, password: T) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// How long the tika server may take to parse the document
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a property of the tesseract OCR config, sent as `X-Tika-OCR<property>`
+    pub fn ocr_property<K: AsRef<str>, V: ToString>(self, property: K, value: V) -> Self {
+        let name = format!("X-Tika-OCR{}", property.as_ref());
+        self.header(name, value)
+    }
+
+    /// Sets a property of the pdf parser config, sent as `X-Tika-PDF<property>`
+    pub fn pdf_property<K: AsRef<str>, V: ToString>(self, property: K, value: V) -> Self {
+        let name = format!("X-Tika-PDF{}", property.as_ref());
+        self.header(name, value)
+    }
+
+    /// Adds any request header
+    pub fn header<K: Into<String>, V: ToString>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// all request headers of these options
+    pub fn to_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                headers.push((name.to_string(), value));
+            }
+        };
+        push("X-Tika-OCRLanguage", self.ocr_language.clone());
+        push("X-Tika-OCRskipOcr", self.skip_ocr.map(|b| b.to_string()));
+        push(
+            "X-Tika-PDFOcrStrategy",
+            self.pdf_ocr_strategy.map(|s| s.as_str().to_string()),
+        );
+        push(
+            "X-Tika-PDFextractInlineImages",
+            self.pdf_extract_inline_images.map(|b| b.to_string()),
+        );
+        push(
+            "X-Tika-PDFsortByPosition",
+            self.pdf_sort_by_position.map(|b| b.to_string()),
+        );
+        push(
+            "X-Tika-PDFextractAnnotationText",
+            self.pdf_extract_annotation_text.map(|b| b.to_string()),
+        );
+        push("Password", self.password.clone());
+        push(
+            "X-Tika-Timeout-Millis",
+            self.timeout.map(|t| t.as_millis().to_string()),
+        );
+        headers.extend(self.headers.iter().cloned());
+        headers
+    }
+}
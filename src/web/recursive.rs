@@ -0,0 +1,479 @@
+use crate::password::ENCRYPTED_EXCEPTION;
+use crate::web::metadata::{Metadata, MetadataValue};
+use std::collections::HashMap;
+
+/// The extracted content of a document
+pub const CONTENT: &str = "X-TIKA:content";
+/// The path of an embedded document within its container, e.g. `/test.zip/image.png`
+pub const EMBEDDED_RESOURCE_PATH: &str = "X-TIKA:embedded_resource_path";
+/// The stack trace of an exception that occurred while parsing the container document, tika 1.x
+pub const EXCEPTION_RUNTIME: &str = "X-TIKA:EXCEPTION:runtime";
+/// The stack trace of an exception that occurred while parsing the container document, tika 2.x
+pub const EXCEPTION_CONTAINER: &str = "X-TIKA:EXCEPTION:container_exception";
+/// The unique path of ids of an embedded document within its container, only set by tika 2.x
+pub const EMBEDDED_ID_PATH: &str = "X-TIKA:embedded_id_path";
+
+/// The content handler the tika server uses to write the `X-TIKA:content` of each document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecursiveHandler {
+    /// XHTML content, the default of `/rmeta`
+    #[default]
+    Xml,
+    /// HTML content
+    Html,
+    /// plain text content
+    Text,
+    /// don't extract any content, only metadata
+    Ignore,
+}
+
+impl RecursiveHandler {
+    /// the path of the `/rmeta` endpoint for this handler
+    pub fn path(&self) -> &'static str {
+        match self {
+            RecursiveHandler::Xml => "rmeta/xml",
+            RecursiveHandler::Html => "rmeta/html",
+            RecursiveHandler::Text => "rmeta/text",
+            RecursiveHandler::Ignore => "rmeta/ignore",
+        }
+    }
+}
+
+/// Options for the recursive parsing of a document and its embedded documents
+#[derive(Debug, Clone, Default)]
+pub struct RecursiveOptions {
+    /// how the content of each document is written
+    pub handler: RecursiveHandler,
+    /// the maximum number of embedded documents to parse
+    pub max_embedded_resources: Option<u32>,
+    /// the maximum number of characters of content to extract over all documents, tika reads
+    /// it as java `int`, larger limits are sent as `i32::MAX`
+    pub write_limit: Option<u64>,
+}
+
+impl RecursiveOptions {
+    /// Options that use the `handler` to write the content of each document
+    pub fn new(handler: RecursiveHandler) -> Self {
+        RecursiveOptions {
+            handler,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the content handler
+    pub fn handler(mut self, handler: RecursiveHandler) -> Self {
+        self.handler = handler;
+        self
+    }
+
+    /// Limits the number of embedded documents that will be parsed
+    pub fn max_embedded_resources(mut self, max: u32) -> Self {
+        self.max_embedded_resources = Some(max);
+        self
+    }
+
+    /// Limits the number of characters that will be extracted
+    pub fn write_limit(mut self, limit: u64) -> Self {
+        self.write_limit = Some(limit);
+        self
+    }
+
+    /// the request headers the tika server reads the limits from
+    pub(crate) fn headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(max) = self.max_embedded_resources {
+            headers.push(("maxEmbeddedResources".to_string(), max.to_string()));
+        }
+        if let Some(limit) = self.write_limit {
+            let limit = limit.min(i32::MAX as u64);
+            headers.push(("writeLimit".to_string(), limit.to_string()));
+        }
+        headers
+    }
+}
+
+/// A document parsed by the `/rmeta` endpoint together with all its embedded documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedDocument {
+    /// all the metadata of this document, without its content
+    pub metadata: Metadata,
+    /// the content of this document, `None` for `RecursiveHandler::Ignore`
+    pub content: Option<String>,
+    /// the path of this document within the container, `None` for the container itself
+    pub embedded_resource_path: Option<String>,
+    /// the documents that are directly embedded in this document
+    pub embedded: Vec<ParsedDocument>,
+    /// the position of this document in the depth first order of the tree
+    index: usize,
+    /// the `index` of the document this document is embedded in
+    parent: Option<usize>,
+}
+
+impl ParsedDocument {
+    /// Rebuilds the hierarchy of the flat list of documents, the tika server returns.
+    /// The first document is the container. An embedded document is attached to the closest
+    /// document whose path is a prefix of its path, or the container if there is none.
+    ///
+    /// Tika 1.x doesn't report unique paths, so embedded documents with the same name share
+    /// their path. Tika lists embedded documents after their own embedded documents, so a
+    /// document is attached to the next document in the list with the path of its parent, or
+    /// else the previous one.
+    pub fn from_list(list: Vec<Metadata>) -> Option<Self> {
+        let mut docs = list.into_iter().map(Node::new);
+        let root = docs.next()?;
+        let nodes: Vec<_> = docs.collect();
+
+        // the indices of the documents by their path, ascending
+        let mut indices: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (idx, node) in nodes.iter().enumerate() {
+            if let Some(key) = node.key.as_deref() {
+                indices.entry(key).or_default().push(idx);
+            }
+        }
+
+        let mut root_children = Vec::new();
+        let mut children = vec![Vec::new(); nodes.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            match parent_index(node.key.as_deref(), idx, &indices) {
+                Some(parent) => children[parent].push(idx),
+                None => root_children.push(idx),
+            }
+        }
+
+        let mut nodes: Vec<_> = nodes.into_iter().map(Some).collect();
+        let mut next = 0;
+        Some(Self::assemble(
+            root,
+            None,
+            &root_children,
+            &mut nodes,
+            &children,
+            &mut next,
+        ))
+    }
+
+    /// attaches the `embedded` nodes to the `node`, which gets the `next` depth first index
+    fn assemble(
+        node: Node,
+        parent: Option<usize>,
+        embedded: &[usize],
+        nodes: &mut [Option<Node>],
+        children: &[Vec<usize>],
+        next: &mut usize,
+    ) -> Self {
+        let index = *next;
+        *next += 1;
+        let embedded = embedded
+            .iter()
+            .filter_map(|&idx| {
+                let child = nodes[idx].take()?;
+                Some(Self::assemble(
+                    child,
+                    Some(index),
+                    &children[idx],
+                    nodes,
+                    children,
+                    next,
+                ))
+            })
+            .collect();
+        ParsedDocument {
+            embedded,
+            index,
+            parent,
+            ..node.doc
+        }
+    }
+
+    /// the parent part of the `embedded_resource_path`, usually the path of the document this
+    /// document is embedded in. Use `parent_of` to look up the actual parent within the tree.
+    /// This is `None` for the container and documents directly embedded in the container.
+    pub fn parent_path(&self) -> Option<&str> {
+        let path = self.embedded_resource_path.as_ref()?;
+        match path.rfind('/') {
+            Some(0) | None => None,
+            Some(idx) => Some(&path[..idx]),
+        }
+    }
+
+    /// the stack trace of the exception that occurred while parsing this document, if any
+    pub fn exception(&self) -> Option<&str> {
+        self.metadata
+            .get_first(EXCEPTION_RUNTIME)
+            .or_else(|| self.metadata.get_first(EXCEPTION_CONTAINER))
+    }
+
+    /// whether this document could not be parsed, because it is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.exception()
+            .is_some_and(|e| e.contains(ENCRYPTED_EXCEPTION))
+    }
+
+    /// whether this is the container document
+    pub fn is_container(&self) -> bool {
+        self.embedded_resource_path.is_none()
+    }
+
+    /// finds the document with the `embedded_resource_path` in this tree
+    pub fn find<T: AsRef<str>>(&self, embedded_resource_path: T) -> Option<&ParsedDocument> {
+        let path = embedded_resource_path.as_ref();
+        if self.embedded_resource_path.as_deref() == Some(path) {
+            return Some(self);
+        }
+        self.embedded.iter().find_map(|doc| doc.find(path))
+    }
+
+    /// Finds the document the `doc` is directly embedded in.
+    /// The parent is looked up by its position in the tree, which takes time proportional to
+    /// the depth of the tree.
+    pub fn parent_of(&self, doc: &ParsedDocument) -> Option<&ParsedDocument> {
+        let parent = self.at(doc.parent?)?;
+        if parent.embedded.iter().any(|child| std::ptr::eq(child, doc)) {
+            Some(parent)
+        } else {
+            None
+        }
+    }
+
+    /// the document at the depth first `index` of the tree, if it is part of this document
+    fn at(&self, index: usize) -> Option<&ParsedDocument> {
+        let mut doc = self;
+        while doc.index != index {
+            // the embedded documents are ordered by their index
+            let next = doc.embedded.partition_point(|child| child.index <= index);
+            doc = doc.embedded.get(next.checked_sub(1)?)?;
+        }
+        Some(doc)
+    }
+
+    /// this document and all its embedded documents in depth first order
+    pub fn documents(&self) -> Vec<&ParsedDocument> {
+        let mut docs = vec![self];
+        for doc in &self.embedded {
+            docs.extend(doc.documents());
+        }
+        docs
+    }
+}
+
+/// The index of the document the document with the `key` at `idx` is embedded in, `None` for
+/// the container. The parent is the closest document whose key is a prefix of the `key`, of
+/// documents with the same key the next one after `idx` or else the previous one.
+fn parent_index(
+    key: Option<&str>,
+    idx: usize,
+    indices: &HashMap<&str, Vec<usize>>,
+) -> Option<usize> {
+    let mut path = key?;
+    while let Some(sep) = path.rfind('/') {
+        path = &path[..sep];
+        if let Some(candidates) = indices.get(path) {
+            let next = candidates.partition_point(|&candidate| candidate < idx);
+            return candidates
+                .get(next)
+                .or_else(|| next.checked_sub(1).and_then(|prev| candidates.get(prev)))
+                .copied();
+        }
+    }
+    None
+}
+
+/// a document of the flat list together with the key used to find its parent
+struct Node {
+    key: Option<String>,
+    doc: ParsedDocument,
+}
+
+impl Node {
+    fn new(mut metadata: Metadata) -> Self {
+        let content = metadata
+            .0
+            .remove(CONTENT)
+            .and_then(|content| content.first().map(str::to_string));
+        let embedded_resource_path = metadata
+            .get_first(EMBEDDED_RESOURCE_PATH)
+            .map(str::to_string);
+        let key = match metadata.get(EMBEDDED_ID_PATH) {
+            Some(MetadataValue::Single(id_path)) => Some(id_path.clone()),
+            _ => embedded_resource_path.clone(),
+        };
+        Node {
+            key,
+            doc: ParsedDocument {
+                metadata,
+                content,
+                embedded_resource_path,
+                embedded: Vec::new(),
+                index: 0,
+                parent: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a document with the `embedded_resource_path` and `embedded_id_path`, its content is
+    /// its number in the list
+    fn doc(content: usize, path: Option<&str>, id_path: Option<&str>) -> Metadata {
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            CONTENT.to_string(),
+            MetadataValue::Single(content.to_string()),
+        );
+        if let Some(path) = path {
+            metadata.insert(
+                EMBEDDED_RESOURCE_PATH.to_string(),
+                MetadataValue::Single(path.to_string()),
+            );
+        }
+        if let Some(id_path) = id_path {
+            metadata.insert(
+                EMBEDDED_ID_PATH.to_string(),
+                MetadataValue::Single(id_path.to_string()),
+            );
+        }
+        Metadata(metadata)
+    }
+
+    fn tree(paths: &[&str]) -> ParsedDocument {
+        let mut list = vec![doc(0, None, None)];
+        list.extend(
+            paths
+                .iter()
+                .enumerate()
+                .map(|(idx, path)| doc(idx + 1, Some(path), None)),
+        );
+        ParsedDocument::from_list(list).unwrap()
+    }
+
+    /// the tree as nested contents, like `0(1(2),3)`
+    fn shape(doc: &ParsedDocument) -> String {
+        let content = doc.content.clone().unwrap_or_default();
+        if doc.embedded.is_empty() {
+            return content;
+        }
+        let embedded: Vec<_> = doc.embedded.iter().map(shape).collect();
+        format!("{}({})", content, embedded.join(","))
+    }
+
+    #[test]
+    fn empty_lists_have_no_container() {
+        assert_eq!(ParsedDocument::from_list(Vec::new()), None);
+    }
+
+    #[test]
+    fn attaches_embedded_documents_to_their_parents() {
+        let doc = tree(&[
+            "/a.zip/b.txt",
+            "/a.zip/c.zip/d.txt",
+            "/a.zip/c.zip",
+            "/a.zip",
+            "/e.png",
+        ]);
+        assert!(doc.is_container());
+        assert_eq!(shape(&doc), "0(4(1,3(2)),5)");
+    }
+
+    #[test]
+    fn attaches_to_the_closest_existing_parent() {
+        let doc = tree(&["/a.zip/dir/b.txt", "/a.zip", "/missing/c.txt"]);
+        assert_eq!(shape(&doc), "0(2(1),3)");
+    }
+
+    #[test]
+    fn attaches_documents_with_the_same_path_to_their_own_parent() {
+        // two attachments named `a.zip`, tika lists them after their embedded documents
+        let doc = tree(&[
+            "/a.zip/b.txt",
+            "/a.zip",
+            "/a.zip/c.txt",
+            "/a.zip/d.txt",
+            "/a.zip",
+        ]);
+        assert_eq!(shape(&doc), "0(2(1),5(3,4))");
+    }
+
+    #[test]
+    fn attaches_to_previous_parents_with_the_same_path() {
+        let doc = tree(&["/a.zip", "/a.zip/b.txt", "/a.zip", "/a.zip/c.txt"]);
+        assert_eq!(shape(&doc), "0(1,3(2,4))");
+    }
+
+    #[test]
+    fn prefers_the_unique_id_paths_of_tika_2() {
+        let list = vec![
+            doc(0, None, None),
+            doc(1, Some("/a.zip"), Some("/1")),
+            doc(2, Some("/a.zip/b.txt"), Some("/1/2")),
+            doc(3, Some("/a.zip"), Some("/3")),
+            doc(4, Some("/a.zip/b.txt"), Some("/3/4")),
+        ];
+        let doc = ParsedDocument::from_list(list).unwrap();
+        assert_eq!(shape(&doc), "0(1(2),3(4))");
+    }
+
+    #[test]
+    fn navigates_the_tree() {
+        let doc = tree(&["/a.zip/b.txt", "/a.zip", "/c.png"]);
+        let b = doc.find("/a.zip/b.txt").unwrap();
+        assert_eq!(b.parent_path(), Some("/a.zip"));
+        let a = doc.parent_of(b).unwrap();
+        assert_eq!(a.embedded_resource_path.as_deref(), Some("/a.zip"));
+        assert_eq!(a.parent_path(), None);
+        assert!(std::ptr::eq(doc.parent_of(a).unwrap(), &doc));
+        assert!(doc.parent_of(&doc).is_none());
+        assert!(a.parent_of(b).is_some());
+        assert!(b.parent_of(a).is_none());
+        assert!(doc.find("/missing").is_none());
+        let contents: Vec<_> = doc
+            .documents()
+            .iter()
+            .map(|doc| doc.content.clone().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["0", "2", "1", "3"]);
+    }
+
+    #[test]
+    fn builds_large_flat_trees() {
+        let paths: Vec<_> = (0..20_000).map(|idx| format!("/{}.txt", idx)).collect();
+        let paths: Vec<_> = paths.iter().map(String::as_str).collect();
+        let doc = tree(&paths);
+        assert_eq!(doc.embedded.len(), 20_000);
+        for embedded in &doc.embedded {
+            assert!(std::ptr::eq(doc.parent_of(embedded).unwrap(), &doc));
+        }
+    }
+
+    #[test]
+    fn clamps_write_limits_to_java_ints() {
+        let headers = RecursiveOptions::default().write_limit(1 << 40).headers();
+        assert_eq!(
+            headers,
+            vec![("writeLimit".to_string(), i32::MAX.to_string())]
+        );
+        let headers = RecursiveOptions::default().write_limit(1000).headers();
+        assert_eq!(
+            headers,
+            vec![("writeLimit".to_string(), "1000".to_string())]
+        );
+    }
+
+    #[test]
+    fn detects_encrypted_documents() {
+        let mut metadata = doc(0, None, None);
+        metadata.0.insert(
+            EXCEPTION_CONTAINER.to_string(),
+            MetadataValue::Single(format!(
+                "org.apache.tika.exception.{}: x",
+                ENCRYPTED_EXCEPTION
+            )),
+        );
+        let doc = ParsedDocument::from_list(vec![metadata]).unwrap();
+        assert!(doc.exception().is_some());
+        assert!(doc.is_encrypted());
+        assert!(!tree(&[]).is_encrypted());
+    }
+}
@@ -0,0 +1,564 @@
+use crate::document::Document;
+use crate::error::{Error, Result};
+use crate::password::{DocumentInfo, PasswordProvider};
+use crate::web::config::MimeType;
+use crate::web::metadata::{Metadata, MetadataValue};
+use crate::web::options::ParseOptions;
+use crate::web::recursive::{ParsedDocument, RecursiveHandler, RecursiveOptions};
+use crate::web::response;
+use crate::web::structured::StructuredDocument;
+use crate::web::translate::{Language, Translation, Translator};
+use crate::web::unpack::{ArchiveFormat, UnpackedResources};
+use reqwest::StatusCode;
+use std::vec;
+
+// The requests that send a document to the tika server are described below, independent of
+// the http client. The blocking and the async client only send the `Outgoing` requests a
+// `ParseFlow` asks for and hand it the responses, so both share how documents are sent,
+// retried with passwords and how the responses are parsed.
+
+/// A request that sends a document to an endpoint of the tika server
+pub(crate) struct DocumentRequest<R> {
+    path: String,
+    accept: &'static str,
+    headers: Vec<(String, String)>,
+    parse: Box<dyn Fn(Vec<u8>) -> Result<R> + Send + Sync>,
+    /// the result of a `404 Not Found` response, if it is no error
+    not_found: Option<fn() -> R>,
+    /// whether encrypted documents are detected and retried with passwords
+    decrypt: bool,
+}
+
+impl<R> DocumentRequest<R> {
+    fn new<F>(path: String, accept: &'static str, headers: Vec<(String, String)>, parse: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> Result<R> + Send + Sync + 'static,
+    {
+        DocumentRequest {
+            path,
+            accept,
+            headers,
+            parse: Box::new(parse),
+            not_found: None,
+            decrypt: true,
+        }
+    }
+
+    /// a request that doesn't detect encrypted documents
+    fn plain(mut self) -> Self {
+        self.decrypt = false;
+        self
+    }
+}
+
+/// the plain text of the document, see `TikaClient::extract_text`
+pub(crate) fn text(options: &ParseOptions) -> DocumentRequest<String> {
+    DocumentRequest::new(
+        "tika".to_string(),
+        "text/plain",
+        options.to_headers(),
+        response::text,
+    )
+}
+
+/// the structure of the document, see `TikaClient::extract_structured`
+pub(crate) fn structured(options: &ParseOptions) -> DocumentRequest<StructuredDocument> {
+    DocumentRequest::new(
+        "tika".to_string(),
+        "text/html",
+        options.to_headers(),
+        |body| StructuredDocument::from_xhtml(&response::text(body)?),
+    )
+}
+
+/// the metadata of the document, see `TikaClient::metadata`
+pub(crate) fn metadata(options: &ParseOptions) -> DocumentRequest<Metadata> {
+    DocumentRequest::new(
+        "meta".to_string(),
+        "application/json",
+        options.to_headers(),
+        response::metadata,
+    )
+}
+
+/// a single metadata `field` of the document, see `TikaClient::metadata_field`
+pub(crate) fn metadata_field(
+    field: &str,
+    options: &ParseOptions,
+) -> DocumentRequest<Option<MetadataValue>> {
+    let name = field.to_string();
+    DocumentRequest {
+        not_found: Some(|| None),
+        ..DocumentRequest::new(
+            format!("meta/{}", path_segment(field)),
+            "application/json",
+            options.to_headers(),
+            move |body| response::metadata_field(body, &name),
+        )
+    }
+}
+
+/// The `value` percent-encoded as a single path segment, so that fields like `a/b` or
+/// `Last Author` can't change the path of the request.
+fn path_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'@' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// the document and all its embedded documents, see `TikaClient::recursive_metadata`
+pub(crate) fn recursive_metadata(
+    recursive: &RecursiveOptions,
+    options: &ParseOptions,
+) -> DocumentRequest<ParsedDocument> {
+    let mut headers = options.to_headers();
+    headers.extend(recursive.headers());
+    DocumentRequest::new(
+        recursive.handler.path().to_string(),
+        "application/json",
+        headers,
+        response::recursive_metadata,
+    )
+}
+
+/// the embedded resources of the document as archive of the `format`, from the `/unpack`
+/// endpoint at `path`, see `TikaClient::unpack`
+pub(crate) fn unpack(
+    path: &str,
+    format: ArchiveFormat,
+    options: &ParseOptions,
+) -> DocumentRequest<UnpackedResources> {
+    DocumentRequest::new(
+        path.to_string(),
+        format.mime_type(),
+        options.to_headers(),
+        move |archive| UnpackedResources::from_archive(format, archive),
+    )
+}
+
+/// the translation of the document, see `TikaClient::translate`
+pub(crate) fn translate(
+    translator: &Translator,
+    src_lang: Option<Language>,
+    dest_lang: Language,
+) -> DocumentRequest<Translation> {
+    let path = response::translate_path(translator, src_lang.as_ref(), &dest_lang);
+    DocumentRequest::new(path, "text/plain", Vec::new(), move |body| {
+        Ok(Translation {
+            content: response::text(body)?,
+            src_lang: src_lang.clone(),
+            dest_lang: dest_lang.clone(),
+        })
+    })
+    .plain()
+}
+
+/// the mime type of the document, see `TikaClient::detect_mime`
+pub(crate) fn detect_mime() -> DocumentRequest<MimeType> {
+    DocumentRequest::new(
+        "detect/stream".to_string(),
+        "text/plain",
+        Vec::new(),
+        |body| Ok(MimeType::new(response::text(body)?)),
+    )
+    .plain()
+}
+
+/// the language of the document, see `TikaClient::detect_language`
+pub(crate) fn detect_language() -> DocumentRequest<Language> {
+    DocumentRequest::new(
+        "language/stream".to_string(),
+        "text/plain",
+        Vec::new(),
+        response::language,
+    )
+    .plain()
+}
+
+/// A `PUT` request of the document, that a client sends for a `ParseFlow`
+#[derive(Debug, Clone)]
+pub(crate) struct Outgoing {
+    /// the path of the endpoint
+    pub(crate) path: String,
+    /// the `Accept` header
+    pub(crate) accept: &'static str,
+    /// the other headers
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+/// What a client does next for a `ParseFlow`
+pub(crate) enum Step<R> {
+    /// send the document again with the `Outgoing` request
+    Send(Outgoing),
+    /// the result of the request
+    Done(Result<R>),
+}
+
+enum State {
+    /// the document was sent as requested
+    Sent,
+    /// the document was sent to `/rmeta/ignore`, after the request failed with the `Error`
+    /// without telling why. `retrying` holds the passwords left to try, if the failed request
+    /// was sent with a password
+    Probing {
+        err: Error,
+        retrying: Option<vec::IntoIter<String>>,
+    },
+    /// the document was sent with the `password`, the rest is left to try
+    Retrying {
+        password: String,
+        passwords: vec::IntoIter<String>,
+    },
+}
+
+/// Sends a `DocumentRequest` of the `doc`.
+///
+/// If the tika server reports that the document is encrypted, an `ErrorKind::Encrypted`
+/// error is returned, unless one of the passwords of the `PasswordProvider` decrypts the
+/// document. Passwords can only be tried for resendable documents.
+///
+/// Each response is classified on its own: a `422 Unprocessable Entity` that names another
+/// exception, like of a corrupt document, is returned as `ErrorKind::Status` error, also
+/// while passwords are tried. Tika only names the exception if it returns stack traces, so
+/// for `422` responses without body the document is probed at `/rmeta/ignore`, but only if
+/// there is a `PasswordProvider` to retry an encrypted document with.
+pub(crate) struct ParseFlow<'a, R> {
+    request: DocumentRequest<R>,
+    doc: &'a Document,
+    provider: Option<&'a dyn PasswordProvider>,
+    state: State,
+}
+
+impl<'a, R> ParseFlow<'a, R> {
+    pub(crate) fn new(
+        request: DocumentRequest<R>,
+        doc: &'a Document,
+        provider: Option<&'a dyn PasswordProvider>,
+    ) -> Self {
+        ParseFlow {
+            request,
+            doc,
+            provider,
+            state: State::Sent,
+        }
+    }
+
+    /// the first request to send
+    pub(crate) fn start(&self) -> Outgoing {
+        let mut headers = self.request.headers.clone();
+        headers.extend(self.doc.headers());
+        Outgoing {
+            path: self.request.path.clone(),
+            accept: self.request.accept,
+            headers,
+        }
+    }
+
+    /// Handles the `outcome` of the last request, either the body of a successful response or
+    /// the error, and tells what to do next
+    pub(crate) fn next(&mut self, outcome: Result<Vec<u8>>) -> Step<R> {
+        match std::mem::replace(&mut self.state, State::Sent) {
+            State::Sent => self.sent(outcome),
+            State::Probing { err, retrying } => {
+                match (response::is_encrypted_probe(outcome), retrying) {
+                    (true, None) => self.encrypted(Error::encrypted(err.to_string())),
+                    (true, Some(passwords)) => self.rejected(passwords),
+                    (false, _) => self.done(Err(err)),
+                }
+            }
+            State::Retrying {
+                password,
+                passwords,
+            } => {
+                let err = match outcome.and_then(&self.request.parse) {
+                    Err(err) => err,
+                    parsed => return self.done(parsed),
+                };
+                if err.is_encrypted() {
+                    return self.rejected(passwords);
+                }
+                match response::is_encrypted_error(&err) {
+                    Some(true) => self.rejected(passwords),
+                    Some(false) => self.done(Err(err)),
+                    None => self.probe(err, Some(password), Some(passwords)),
+                }
+            }
+        }
+    }
+
+    /// handles the response of the request without password
+    fn sent(&mut self, outcome: Result<Vec<u8>>) -> Step<R> {
+        let err = match outcome {
+            Ok(body) => match (self.request.parse)(body) {
+                Err(err) if self.request.decrypt && err.is_encrypted() => err,
+                parsed => return self.done(parsed),
+            },
+            Err(err) if !self.request.decrypt => return self.done(Err(err)),
+            Err(err) => err,
+        };
+        if err.is_encrypted() {
+            return self.encrypted(err);
+        }
+        match response::is_encrypted_error(&err) {
+            Some(true) => self.encrypted(Error::encrypted(err.to_string())),
+            None if self.can_retry() => self.probe(err, None, None),
+            _ => self.done(Err(err)),
+        }
+    }
+
+    /// whether an encrypted document can be retried with passwords
+    fn can_retry(&self) -> bool {
+        self.provider.is_some() && self.doc.is_resendable()
+    }
+
+    /// sends the document to `/rmeta/ignore`, to find out whether the request failed with
+    /// the `err` because the document is encrypted
+    fn probe(
+        &mut self,
+        err: Error,
+        password: Option<String>,
+        retrying: Option<vec::IntoIter<String>>,
+    ) -> Step<R> {
+        let mut headers = RecursiveOptions::new(RecursiveHandler::Ignore).headers();
+        headers.extend(self.doc.headers());
+        headers.extend(password.map(|password| ("Password".to_string(), password)));
+        self.state = State::Probing { err, retrying };
+        Step::Send(Outgoing {
+            path: RecursiveHandler::Ignore.path().to_string(),
+            accept: "application/json",
+            headers,
+        })
+    }
+
+    /// starts to retry the encrypted document with the passwords of the provider
+    fn encrypted(&mut self, err: Error) -> Step<R> {
+        let provider = match self.provider {
+            Some(provider) if self.doc.is_resendable() => provider,
+            _ => return self.done(Err(err)),
+        };
+        let sha256 = match self.doc.sha256() {
+            Ok(sha256) => sha256.unwrap_or_default(),
+            Err(e) => return self.done(Err(e)),
+        };
+        let info = DocumentInfo {
+            name: self.doc.name(),
+            sha256,
+        };
+        self.retry(provider.passwords(&info).into_iter())
+    }
+
+    /// the last password didn't decrypt the document
+    fn rejected(&mut self, passwords: vec::IntoIter<String>) -> Step<R> {
+        debug!("Password was rejected by the tika server, trying next password.");
+        self.retry(passwords)
+    }
+
+    /// sends the document with the next password
+    fn retry(&mut self, mut passwords: vec::IntoIter<String>) -> Step<R> {
+        let password = match passwords.next() {
+            Some(password) => password,
+            None => {
+                return self.done(Err(Error::encrypted(
+                    "None of the passwords of the password provider could decrypt the document",
+                )))
+            }
+        };
+        let mut outgoing = self.start();
+        outgoing
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("Password"));
+        outgoing
+            .headers
+            .push(("Password".to_string(), password.clone()));
+        self.state = State::Retrying {
+            password,
+            passwords,
+        };
+        Step::Send(outgoing)
+    }
+
+    /// the final result, unsuccessful responses without result are turned into `not_found`
+    fn done(&self, parsed: Result<R>) -> Step<R> {
+        match (parsed, self.request.not_found) {
+            (Err(ref e), Some(not_found)) if e.status_code() == Some(StatusCode::NOT_FOUND) => {
+                Step::Done(Ok(not_found()))
+            }
+            (parsed, _) => Step::Done(parsed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::ENCRYPTED_EXCEPTION;
+    use std::io::Cursor;
+    use url::Url;
+
+    fn unprocessable(msg: &str) -> Result<Vec<u8>> {
+        Err(Error::status(StatusCode::UNPROCESSABLE_ENTITY, msg))
+    }
+
+    fn encrypted() -> Result<Vec<u8>> {
+        unprocessable(&format!(
+            "org.apache.tika.exception.{}",
+            ENCRYPTED_EXCEPTION
+        ))
+    }
+
+    fn sent<R>(step: Step<R>) -> Outgoing {
+        match step {
+            Step::Send(outgoing) => outgoing,
+            Step::Done(_) => panic!("expected another request"),
+        }
+    }
+
+    fn done<R>(step: Step<R>) -> Result<R> {
+        match step {
+            Step::Done(parsed) => parsed,
+            Step::Send(outgoing) => panic!("unexpected request to {}", outgoing.path),
+        }
+    }
+
+    fn password(outgoing: &Outgoing) -> Option<&str> {
+        outgoing
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Password")
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn passwords() -> Vec<String> {
+        vec!["first".to_string(), "second".to_string()]
+    }
+
+    #[test]
+    fn parses_successful_responses() {
+        let doc = Document::from("text");
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, None);
+        assert_eq!(flow.start().path, "tika");
+        assert_eq!(done(flow.next(Ok(b"text".to_vec()))).unwrap(), "text");
+    }
+
+    #[test]
+    fn does_not_probe_without_password_provider() {
+        let doc = Document::from("text");
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, None);
+        let err = done(flow.next(unprocessable(""))).unwrap_err();
+        assert_eq!(err.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+        assert!(!err.is_encrypted());
+    }
+
+    #[test]
+    fn reports_encrypted_documents_without_password_provider() {
+        let doc = Document::from("text");
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, None);
+        assert!(done(flow.next(encrypted())).unwrap_err().is_encrypted());
+    }
+
+    #[test]
+    fn probes_unexplained_errors_and_retries_with_passwords() {
+        let doc = Document::from("text");
+        let provider = passwords();
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        let probe = sent(flow.next(unprocessable("")));
+        assert_eq!(probe.path, RecursiveHandler::Ignore.path());
+        assert_eq!(password(&probe), None);
+
+        let retry = sent(flow.next(encrypted()));
+        assert_eq!(retry.path, "tika");
+        assert_eq!(password(&retry), Some("first"));
+
+        // the rejected password is probed with the password
+        let probe = sent(flow.next(unprocessable("")));
+        assert_eq!(probe.path, RecursiveHandler::Ignore.path());
+        assert_eq!(password(&probe), Some("first"));
+
+        let retry = sent(flow.next(encrypted()));
+        assert_eq!(password(&retry), Some("second"));
+        assert_eq!(done(flow.next(Ok(b"text".to_vec()))).unwrap(), "text");
+    }
+
+    #[test]
+    fn returns_errors_of_documents_that_are_not_encrypted() {
+        let doc = Document::from("text");
+        let provider = passwords();
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        sent(flow.next(unprocessable("")));
+        let err = done(flow.next(Ok(b"[{}]".to_vec()))).unwrap_err();
+        assert_eq!(err.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    #[test]
+    fn reports_corrupt_documents_while_retrying() {
+        let doc = Document::from("text");
+        let provider = passwords();
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        sent(flow.next(encrypted()));
+        let err = done(flow.next(unprocessable(
+            "org.apache.tika.exception.TikaException: bad",
+        )))
+        .unwrap_err();
+        assert_eq!(err.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+        assert!(!err.is_encrypted());
+    }
+
+    #[test]
+    fn reports_encrypted_documents_after_all_passwords() {
+        let doc = Document::from("text");
+        let provider = passwords();
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        assert_eq!(password(&sent(flow.next(encrypted()))), Some("first"));
+        assert_eq!(password(&sent(flow.next(encrypted()))), Some("second"));
+        assert!(done(flow.next(encrypted())).unwrap_err().is_encrypted());
+    }
+
+    #[test]
+    fn does_not_retry_streamed_documents() {
+        let doc = Document::from_reader(Cursor::new(b"text".to_vec()));
+        let provider = passwords();
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        let err = done(flow.next(unprocessable(""))).unwrap_err();
+        assert!(!err.is_encrypted());
+        let mut flow = ParseFlow::new(text(&ParseOptions::default()), &doc, Some(&provider));
+        assert!(done(flow.next(encrypted())).unwrap_err().is_encrypted());
+    }
+
+    #[test]
+    fn missing_metadata_fields_are_none() {
+        let doc = Document::from("text");
+        let request = metadata_field("dc:title", &ParseOptions::default());
+        let mut flow = ParseFlow::new(request, &doc, None);
+        assert_eq!(flow.start().path, "meta/dc:title");
+        let parsed = done(flow.next(Err(Error::status(StatusCode::NOT_FOUND, ""))));
+        assert!(parsed.unwrap().is_none());
+    }
+
+    #[test]
+    fn metadata_fields_are_single_path_segments() {
+        let doc = Document::from("text");
+        let request = metadata_field("a/../b c?d", &ParseOptions::default());
+        let path = ParseFlow::new(request, &doc, None).start().path;
+        assert_eq!(path, "meta/a%2F..%2Fb%20c%3Fd");
+        let url = Url::parse("http://localhost:9998/")
+            .unwrap()
+            .join(&path)
+            .unwrap();
+        assert_eq!(url.path(), "/meta/a%2F..%2Fb%20c%3Fd");
+        assert_eq!(url.query(), None);
+    }
+
+    #[test]
+    fn detection_is_not_retried() {
+        let doc = Document::from("text");
+        let provider = passwords();
+        let mut flow = ParseFlow::new(detect_mime(), &doc, Some(&provider));
+        assert!(!done(flow.next(encrypted())).unwrap_err().is_encrypted());
+    }
+}
@@ -1,4 +1,11 @@
-use crate::web::config::{Detector, MimeType, Parser};
+use crate::error::{Error, ErrorKind, Result};
+use crate::password::ENCRYPTED_EXCEPTION;
+use crate::web::config::{Detector, MimeType, MimeTypeInner, Parser};
+use crate::web::metadata::{Metadata, MetadataValue};
+use crate::web::recursive::ParsedDocument;
+use crate::web::translate::{Language, Translator};
+use reqwest::StatusCode;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerConfig {
@@ -7,3 +14,105 @@ pub enum ServerConfig {
     MimeTypes(Vec<MimeType>),
     Endpoints,
 }
+
+// The responses of the tika server are read into memory by the blocking and the async client
+// and then parsed by the functions below.
+
+/// the body of a `text/plain` response
+pub(crate) fn text(body: Vec<u8>) -> Result<String> {
+    Ok(String::from_utf8(body)
+        .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
+}
+
+/// the body of a `/meta` response
+pub(crate) fn metadata(body: Vec<u8>) -> Result<Metadata> {
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// the `field` of a `/meta/{field}` response
+pub(crate) fn metadata_field(body: Vec<u8>, field: &str) -> Result<Option<MetadataValue>> {
+    Ok(metadata(body)?.0.remove(field))
+}
+
+/// the body of a `/rmeta` response, fails if the container document is encrypted
+pub(crate) fn recursive_metadata(body: Vec<u8>) -> Result<ParsedDocument> {
+    let list: Vec<Metadata> = serde_json::from_slice(&body)?;
+    let doc = ParsedDocument::from_list(list)
+        .ok_or_else(|| Error::server("Failed to parse document. Got empty response."))?;
+    if doc.is_encrypted() {
+        Err(Error::encrypted(doc.exception().unwrap_or_default()))
+    } else {
+        Ok(doc)
+    }
+}
+
+/// the body of a `/language/stream` response
+pub(crate) fn language(body: Vec<u8>) -> Result<Language> {
+    let lang = text(body)?;
+    if lang.is_empty() {
+        Err(Error::server(
+            "Failed to detect language. Got empty response.",
+        ))
+    } else {
+        Ok(lang.into())
+    }
+}
+
+/// the body of a `/mime-types` response
+pub(crate) fn mime_types(body: Vec<u8>) -> Result<Vec<MimeType>> {
+    let mimes: HashMap<String, serde_json::Value> = serde_json::from_slice(&body)?;
+
+    let mimes: ::std::result::Result<Vec<_>, _> = mimes
+        .into_iter()
+        .map(|(identifier, value)| {
+            serde_json::from_value::<MimeTypeInner>(value).map(|x| MimeType {
+                identifier,
+                supertype: x.supertype,
+                alias: x.alias,
+                parser: x.parser,
+            })
+        })
+        .collect();
+
+    Ok(mimes?)
+}
+
+/// the path of the translate endpoint
+pub(crate) fn translate_path(
+    translator: &Translator,
+    src_lang: Option<&Language>,
+    dest_lang: &Language,
+) -> String {
+    let mut path = format!("translate/all/{}/", translator.as_str());
+    if let Some(src_lang) = src_lang {
+        path = format!("{}{}/", path, src_lang.0);
+    }
+    path + &dest_lang.0
+}
+
+/// Whether the error of a parse request was caused by an encrypted document.
+///
+/// Tika only includes the exception in `422 Unprocessable Entity` responses if it is
+/// configured to return stack traces. `None` indicates that the response does not tell and
+/// the document needs to be probed with `/rmeta`.
+pub(crate) fn is_encrypted_error(err: &Error) -> Option<bool> {
+    if err.status_code() != Some(StatusCode::UNPROCESSABLE_ENTITY) {
+        return Some(false);
+    }
+    match err.kind() {
+        ErrorKind::Status { msg, .. } if msg.contains(ENCRYPTED_EXCEPTION) => Some(true),
+        ErrorKind::Status { msg, .. } if !msg.trim().is_empty() => Some(false),
+        _ => None,
+    }
+}
+
+/// whether the response of the `/rmeta/ignore` probe reports an encrypted document
+pub(crate) fn is_encrypted_probe(probe: Result<Vec<u8>>) -> bool {
+    match probe.and_then(|body| Ok(serde_json::from_slice::<Vec<Metadata>>(&body)?)) {
+        Ok(list) => ParsedDocument::from_list(list).is_some_and(|doc| doc.is_encrypted()),
+        Err(err) => match err.kind() {
+            ErrorKind::Status { msg, .. } => msg.contains(ENCRYPTED_EXCEPTION),
+            _ => false,
+        },
+    }
+}
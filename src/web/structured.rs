@@ -0,0 +1,738 @@
+use crate::error::Result;
+use crate::web::metadata::{Metadata, MetadataValue};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::mem;
+
+/// html elements that never have content and are not closed in tika's html output
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// elements after which the text of a page continues on a new line
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "pre",
+    "blockquote",
+    "address",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "tr",
+    "div",
+    "table",
+    "ul",
+    "ol",
+];
+
+/// A structural element of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    /// a heading of `level` 1 to 6
+    Heading {
+        /// the level of the heading, `1` for `<h1>`
+        level: u8,
+        /// the text of the heading
+        text: String,
+    },
+    /// a paragraph of text
+    Paragraph(String),
+    /// a list with the text of each item
+    List {
+        /// whether this is a `<ol>` list
+        ordered: bool,
+        /// the text of each item
+        items: Vec<String>,
+    },
+    /// a table with the text of each cell per row
+    Table(Vec<Vec<String>>),
+}
+
+impl Element {
+    /// the text of this element, with list items and table rows separated by new lines and
+    /// table cells separated by tabs
+    pub fn text(&self) -> String {
+        match self {
+            Element::Heading { text, .. } | Element::Paragraph(text) => text.clone(),
+            Element::List { items, .. } => items.join("\n"),
+            Element::Table(rows) => rows
+                .iter()
+                .map(|row| row.join("\t"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// An `Element` together with the page it is located on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// the number of the page, starting at 1, `None` if the document has no pages
+    pub page: Option<u32>,
+    /// the element
+    pub element: Element,
+}
+
+/// A hyperlink of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// the target of the link
+    pub href: String,
+    /// the text of the link
+    pub text: String,
+    /// the number of the page the link is located on
+    pub page: Option<u32>,
+}
+
+/// The text of a single page of a paged document, like a pdf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// the number of the page, starting at 1
+    pub number: u32,
+    /// the text of the page, including the text recognized by OCR
+    pub text: String,
+    /// whether the page has no text of its own, like a scanned page.
+    /// The `text` of such a page is only available, if `ocr_applied`.
+    pub image_only: bool,
+    /// whether the tika server applied OCR to this page
+    pub ocr_applied: bool,
+}
+
+/// The structure of a document, parsed from the XHTML the tika server returns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StructuredDocument {
+    /// the `<title>` of the document
+    pub title: Option<String>,
+    /// the metadata, tika includes as `<meta>` elements
+    pub metadata: Metadata,
+    /// all elements in document order
+    pub blocks: Vec<Block>,
+    /// all links in document order
+    pub links: Vec<Link>,
+    /// the text of each `<div class="page">` element, empty if the document has no pages
+    pub pages: Vec<Page>,
+}
+
+impl StructuredDocument {
+    /// Parses the XHTML or HTML output of the tika server.
+    pub fn from_xhtml(xhtml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xhtml);
+        reader.config_mut().check_end_names = false;
+        let mut builder = Builder::default();
+        loop {
+            match reader.read_event()? {
+                Event::Start(ref e) => {
+                    let name = tag_name(e.local_name().as_ref());
+                    builder.start(&name, e);
+                    if VOID_ELEMENTS.contains(&name.as_str()) {
+                        builder.end(&name);
+                    }
+                }
+                Event::Empty(ref e) => {
+                    let name = tag_name(e.local_name().as_ref());
+                    builder.start(&name, e);
+                    builder.end(&name);
+                }
+                Event::End(ref e) => {
+                    let name = tag_name(e.local_name().as_ref());
+                    if !VOID_ELEMENTS.contains(&name.as_str()) {
+                        builder.end(&name);
+                    }
+                }
+                Event::Text(ref e) => match e.unescape() {
+                    Ok(text) => builder.text(&text),
+                    Err(_) => builder.text(&String::from_utf8_lossy(e)),
+                },
+                Event::CData(ref e) => builder.text(&String::from_utf8_lossy(e)),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    /// the number of pages, `0` if the document has no pages
+    pub fn page_count(&self) -> u32 {
+        self.pages.len() as u32
+    }
+
+    /// all blocks on the page with the `number`, starting at 1
+    pub fn blocks_on_page(&self, number: u32) -> impl Iterator<Item = &Block> {
+        self.blocks
+            .iter()
+            .filter(move |block| block.page == Some(number))
+    }
+
+    /// all headings with their level
+    pub fn headings(&self) -> impl Iterator<Item = (u8, &str)> {
+        self.blocks.iter().filter_map(|block| match &block.element {
+            Element::Heading { level, text } => Some((*level, text.as_str())),
+            _ => None,
+        })
+    }
+
+    /// the text of all blocks, separated by empty lines
+    pub fn text(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| block.element.text())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn tag_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_lowercase()
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .filter_map(|attr| attr.ok())
+        .find(|attr| attr.key.as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// the innermost list item or table cell of the open elements
+fn enclosing_item(stack: &[String]) -> Option<&str> {
+    stack
+        .iter()
+        .rev()
+        .map(String::as_str)
+        .find(|open| matches!(*open, "li" | "td" | "th"))
+}
+
+/// collapses all whitespace into single spaces
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// the state of a table while its rows are read
+#[derive(Default)]
+struct TableState {
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+}
+
+/// the state of a list while its items are read
+struct ListState {
+    ordered: bool,
+    items: Vec<String>,
+}
+
+/// Assembles the `StructuredDocument` from the elements of the XHTML
+#[derive(Default)]
+struct Builder {
+    doc: StructuredDocument,
+    /// names of all open elements
+    stack: Vec<String>,
+    /// depth of the stack at which the current page was opened
+    page_depth: Option<usize>,
+    page: Option<u32>,
+    /// the text of the current page
+    page_text: String,
+    /// whether the current page has any text outside of OCR output
+    page_native: bool,
+    /// whether the current page contains OCR output
+    page_ocr: bool,
+    /// depth of the stack at which the current OCR output was opened
+    ocr_depth: Option<usize>,
+    /// text of the current heading, paragraph, list item or table cell
+    text: Option<String>,
+    /// the text of the enclosing list items and table cells, while a nested one is read
+    saved: Vec<Option<String>>,
+    /// text outside of any block
+    stray: String,
+    lists: Vec<ListState>,
+    tables: Vec<TableState>,
+    link: Option<(String, String)>,
+    title: Option<String>,
+    /// number of open elements, whose text is ignored
+    skip: usize,
+}
+
+impl Builder {
+    fn start(&mut self, name: &str, e: &BytesStart) {
+        self.stack.push(name.to_string());
+        match name {
+            "script" | "style" => self.skip += 1,
+            "title" => self.title = Some(String::new()),
+            "meta" => {
+                if let (Some(key), Some(value)) = (attribute(e, b"name"), attribute(e, b"content"))
+                {
+                    self.add_metadata(key, value);
+                }
+            }
+            "div" => {
+                let class = attribute(e, b"class").unwrap_or_default();
+                let has_class = |name| class.split_whitespace().any(|c| c == name);
+                if has_class("page") && self.page.is_none() {
+                    self.flush_stray();
+                    self.page = Some(self.doc.pages.len() as u32 + 1);
+                    self.page_depth = Some(self.stack.len());
+                } else if has_class("ocr") && self.ocr_depth.is_none() {
+                    self.page_ocr = true;
+                    self.ocr_depth = Some(self.stack.len());
+                }
+            }
+            "p" | "pre" | "blockquote" | "address" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if self.in_item() {
+                    self.push_text(" ");
+                } else {
+                    self.flush_stray();
+                    self.text = Some(String::new());
+                }
+            }
+            "ul" | "ol" => {
+                match enclosing_item(&self.stack[..self.stack.len() - 1]) {
+                    // the items of nested lists follow the text of their item
+                    Some("li") => {
+                        self.flush_item();
+                        self.text = Some(String::new());
+                    }
+                    Some(_) => {}
+                    None => self.flush_stray(),
+                }
+                self.lists.push(ListState {
+                    ordered: name == "ol",
+                    items: Vec::new(),
+                });
+            }
+            "li" | "td" | "th" => {
+                self.saved.push(self.text.take());
+                self.text = Some(String::new());
+            }
+            "table" => {
+                if self.text.is_none() {
+                    self.flush_stray();
+                }
+                self.tables.push(TableState::default());
+            }
+            "tr" => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.row.clear();
+                }
+            }
+            "br" => {
+                self.push_text("\n");
+                self.page_text.push('\n');
+            }
+            "a" => {
+                if let Some(href) = attribute(e, b"href") {
+                    self.link = Some((href, String::new()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        // tolerate unbalanced html by closing all elements up to the matching one
+        match self.stack.iter().rposition(|open| open == name) {
+            Some(idx) => {
+                let closed = self.stack.split_off(idx);
+                for open in closed[1..].iter().rev() {
+                    self.close(open);
+                }
+            }
+            None => return,
+        }
+        self.close(name);
+    }
+
+    fn close(&mut self, name: &str) {
+        if self.page.is_some() {
+            if BLOCK_ELEMENTS.contains(&name) {
+                self.page_text.push('\n');
+            } else if name == "td" || name == "th" {
+                self.page_text.push('\t');
+            }
+        }
+        match name {
+            "script" | "style" => self.skip = self.skip.saturating_sub(1),
+            "title" => {
+                if let Some(title) = self.title.take() {
+                    let title = normalize(&title);
+                    if !title.is_empty() {
+                        self.doc.title = Some(title);
+                    }
+                }
+            }
+            "div" => {
+                if self.ocr_depth.is_some_and(|depth| self.stack.len() < depth) {
+                    self.ocr_depth = None;
+                }
+                if self
+                    .page_depth
+                    .is_some_and(|depth| self.stack.len() < depth)
+                {
+                    self.flush_stray();
+                    self.finish_page();
+                }
+            }
+            "p" | "blockquote" | "address" if !self.in_item() => {
+                if let Some(text) = self.text.take() {
+                    self.push_element(Element::Paragraph(normalize(&text)));
+                }
+            }
+            "pre" if !self.in_item() => {
+                if let Some(text) = self.text.take() {
+                    let text = text.trim_matches('\n').to_string();
+                    self.push_element(Element::Paragraph(text));
+                }
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if !self.in_item() => {
+                if let Some(text) = self.text.take() {
+                    let level = name[1..].parse().unwrap_or(1);
+                    let text = normalize(&text);
+                    if !text.is_empty() {
+                        self.push_element(Element::Heading { level, text });
+                    }
+                }
+            }
+            "li" => {
+                self.flush_item();
+                self.text = self.saved.pop().flatten();
+            }
+            "ul" | "ol" => {
+                if let Some(list) = self.lists.pop() {
+                    match enclosing_item(&self.stack) {
+                        // nested lists are merged into their parent list
+                        Some("li") => {
+                            if let Some(parent) = self.lists.last_mut() {
+                                parent.items.extend(list.items);
+                            }
+                        }
+                        // lists within table cells are part of the text of the cell
+                        Some(_) => self.push_text(&format!(" {} ", list.items.join(" "))),
+                        None if !list.items.is_empty() => self.push_element(Element::List {
+                            ordered: list.ordered,
+                            items: list.items,
+                        }),
+                        None => {}
+                    }
+                }
+            }
+            "td" | "th" => {
+                let cell = self.text.take().map(|text| normalize(&text));
+                if let (Some(table), Some(cell)) = (self.tables.last_mut(), cell) {
+                    table.row.push(cell);
+                }
+                self.text = self.saved.pop().flatten();
+            }
+            "tr" => {
+                if let Some(table) = self.tables.last_mut() {
+                    if !table.row.is_empty() {
+                        let row = mem::take(&mut table.row);
+                        table.rows.push(row);
+                    }
+                }
+            }
+            "table" => {
+                if let Some(table) = self.tables.pop() {
+                    if table.rows.is_empty() {
+                        return;
+                    }
+                    if enclosing_item(&self.stack).is_some() {
+                        // nested tables are part of the text of their cell or list item
+                        let cells: Vec<_> = table.rows.concat();
+                        self.push_text(&format!(" {} ", cells.join(" ")));
+                    } else {
+                        self.push_element(Element::Table(table.rows));
+                    }
+                }
+            }
+            "a" => {
+                if let Some((href, text)) = self.link.take() {
+                    self.doc.links.push(Link {
+                        href,
+                        text: normalize(&text),
+                        page: self.page,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip > 0 {
+            return;
+        }
+        if let Some(title) = self.title.as_mut() {
+            title.push_str(text);
+            return;
+        }
+        if let Some((_, link)) = self.link.as_mut() {
+            link.push_str(text);
+        }
+        if self.stack.iter().any(|open| open == "body") || self.text.is_some() {
+            self.push_text(text);
+        }
+        if self.page.is_some() {
+            self.page_text.push_str(text);
+            if self.ocr_depth.is_none() && !text.trim().is_empty() {
+                self.page_native = true;
+            }
+        }
+    }
+
+    fn finish_page(&mut self) {
+        if let Some(number) = self.page.take() {
+            let text = mem::take(&mut self.page_text);
+            let text = text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.doc.pages.push(Page {
+                number,
+                text,
+                image_only: !mem::take(&mut self.page_native),
+                ocr_applied: mem::take(&mut self.page_ocr),
+            });
+        }
+        self.page_depth = None;
+        self.ocr_depth = None;
+    }
+
+    fn push_text(&mut self, text: &str) {
+        match self.text.as_mut() {
+            Some(current) => current.push_str(text),
+            None => self.stray.push_str(text),
+        }
+    }
+
+    /// whether text is currently collected for a list item or a table cell
+    fn in_item(&self) -> bool {
+        self.text.is_some()
+            && self
+                .stack
+                .iter()
+                .any(|open| open == "li" || open == "td" || open == "th")
+    }
+
+    /// adds the text of the current list item to the innermost list
+    fn flush_item(&mut self) {
+        let text = match self.text.take() {
+            Some(text) => normalize(&text),
+            None => return,
+        };
+        match self.lists.last_mut() {
+            Some(list) if !text.is_empty() => list.items.push(text),
+            Some(_) => {}
+            // an item outside of any list
+            None => self.push_element(Element::Paragraph(text)),
+        }
+    }
+
+    fn flush_stray(&mut self) {
+        let stray = normalize(&mem::take(&mut self.stray));
+        if !stray.is_empty() {
+            self.push_element(Element::Paragraph(stray));
+        }
+    }
+
+    fn push_element(&mut self, element: Element) {
+        if let Element::Paragraph(text) = &element {
+            if text.is_empty() {
+                return;
+            }
+        }
+        self.doc.blocks.push(Block {
+            page: self.page,
+            element,
+        });
+    }
+
+    fn add_metadata(&mut self, key: String, value: String) {
+        let meta = &mut self.doc.metadata.0;
+        let entry = match meta.remove(&key) {
+            None => MetadataValue::Single(value),
+            Some(MetadataValue::Single(first)) => MetadataValue::Multi(vec![first, value]),
+            Some(MetadataValue::Multi(mut values)) => {
+                values.push(value);
+                MetadataValue::Multi(values)
+            }
+        };
+        meta.insert(key, entry);
+    }
+
+    fn finish(mut self) -> StructuredDocument {
+        let open: Vec<_> = self.stack.drain(..).rev().collect();
+        for name in open {
+            self.close(&name);
+        }
+        self.flush_stray();
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> StructuredDocument {
+        let xhtml = format!(
+            "<html xmlns=\"http://www.w3.org/1999/xhtml\"><head>\
+             <meta name=\"Content-Type\" content=\"application/pdf\"/>\
+             <title>Report</title></head><body>{}</body></html>",
+            body
+        );
+        StructuredDocument::from_xhtml(&xhtml).unwrap()
+    }
+
+    fn elements(doc: &StructuredDocument) -> Vec<Element> {
+        doc.blocks
+            .iter()
+            .map(|block| block.element.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parses_headings_and_paragraphs() {
+        let doc = parse("<h1>Intro</h1><p>first\n  line</p>stray<h2>Next</h2>");
+        assert_eq!(doc.title.as_deref(), Some("Report"));
+        assert_eq!(
+            doc.metadata.0.get("Content-Type"),
+            Some(&MetadataValue::Single("application/pdf".to_string()))
+        );
+        assert_eq!(
+            elements(&doc),
+            vec![
+                Element::Heading {
+                    level: 1,
+                    text: "Intro".to_string()
+                },
+                Element::Paragraph("first line".to_string()),
+                Element::Paragraph("stray".to_string()),
+                Element::Heading {
+                    level: 2,
+                    text: "Next".to_string()
+                },
+            ]
+        );
+        assert!(doc.pages.is_empty());
+    }
+
+    #[test]
+    fn parses_pages() {
+        let doc = parse(
+            "<div class=\"page\"><p>one</p><p>two</p></div>\
+             <div class=\"page\"><p>three</p></div>",
+        );
+        assert_eq!(doc.page_count(), 2);
+        assert_eq!(doc.pages[0].text, "one\ntwo");
+        assert!(!doc.pages[0].image_only);
+        assert!(!doc.pages[0].ocr_applied);
+        assert_eq!(doc.pages[1].number, 2);
+        assert_eq!(doc.pages[1].text, "three");
+        assert_eq!(doc.blocks_on_page(2).count(), 1);
+        assert_eq!(doc.blocks[0].page, Some(1));
+    }
+
+    #[test]
+    fn detects_ocr_pages() {
+        let doc = parse(
+            "<div class=\"page\"><p>native</p><div class=\"ocr\">more</div></div>\
+             <div class=\"page\"><div class=\"ocr\">scanned text</div></div>\
+             <div class=\"page\"></div>",
+        );
+        assert_eq!(doc.page_count(), 3);
+        assert!(doc.pages[0].ocr_applied);
+        assert!(!doc.pages[0].image_only);
+        assert_eq!(doc.pages[0].text, "native\nmore");
+        assert!(doc.pages[1].ocr_applied);
+        assert!(doc.pages[1].image_only);
+        assert_eq!(doc.pages[1].text, "scanned text");
+        assert!(!doc.pages[2].ocr_applied);
+        assert!(doc.pages[2].image_only);
+    }
+
+    #[test]
+    fn parses_tables() {
+        let doc = parse(
+            "<table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td><p>2</p></td></tr></table>",
+        );
+        assert_eq!(
+            elements(&doc),
+            vec![Element::Table(vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["1".to_string(), "2".to_string()],
+            ])]
+        );
+    }
+
+    #[test]
+    fn keeps_nested_tables_in_their_cell() {
+        let doc = parse(
+            "<table><tr><td>outer<table><tr><td>inner</td><td>cell</td></tr></table>after</td>\
+             <td>next</td></tr></table><p>end</p>",
+        );
+        assert_eq!(
+            elements(&doc),
+            vec![
+                Element::Table(vec![vec![
+                    "outer inner cell after".to_string(),
+                    "next".to_string()
+                ]]),
+                Element::Paragraph("end".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_nested_lists() {
+        let doc = parse(
+            "<ol><li>a<ul><li>b</li><li>c</li></ul>d</li><li>e</li></ol>\
+             <table><tr><td>x<ul><li>y</li></ul></td></tr></table>",
+        );
+        assert_eq!(
+            elements(&doc),
+            vec![
+                Element::List {
+                    ordered: true,
+                    items: vec!["a", "b", "c", "d", "e"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                },
+                Element::Table(vec![vec!["x y".to_string()]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_links() {
+        let doc = parse(
+            "<p>see <a href=\"https://tika.apache.org\">the\n docs</a></p>\
+             <div class=\"page\"><a href=\"#p2\">next</a><a name=\"anchor\">none</a></div>",
+        );
+        assert_eq!(
+            doc.links,
+            vec![
+                Link {
+                    href: "https://tika.apache.org".to_string(),
+                    text: "the docs".to_string(),
+                    page: None,
+                },
+                Link {
+                    href: "#p2".to_string(),
+                    text: "next".to_string(),
+                    page: Some(1),
+                },
+            ]
+        );
+        assert_eq!(
+            elements(&doc)[0],
+            Element::Paragraph("see the docs".to_string())
+        );
+    }
+}
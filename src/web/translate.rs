@@ -1,4 +1,5 @@
 use crate::error::Result;
+use crate::server::cache::LANGUAGE_KEYS_DIR;
 use std::path::Path;
 use std::{fs, path::PathBuf};
 
@@ -99,7 +100,7 @@ impl TranslatorProperties {
         match self {
             TranslatorProperties::Dir(dir) => Ok(dir.clone()),
             TranslatorProperties::Keys(keys) => {
-                let root = tika_path.as_ref().join("language-keys");
+                let root = tika_path.as_ref().join(LANGUAGE_KEYS_DIR);
 
                 let dir = root.join("org/apache/tika/language/translate");
 
@@ -121,9 +122,10 @@ impl TranslatorProperties {
 }
 
 /// Available translators on the tika server
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 #[allow(missing_docs)]
 pub enum Translator {
+    #[default]
     Lingo24,
     Google,
     Yandex,
@@ -170,9 +172,3 @@ impl Translator {
         }
     }
 }
-
-impl Default for Translator {
-    fn default() -> Self {
-        Translator::Lingo24
-    }
-}
@@ -0,0 +1,655 @@
+use crate::error::Result;
+use crate::web::metadata::{Metadata, MetadataValue};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::{fs, slice, vec};
+
+/// The name of the entry that contains the text of the container document in `/unpack/all`
+pub const TEXT_ENTRY: &str = "__TEXT__";
+/// The name of the entry that contains the metadata of the container document in `/unpack/all`
+pub const METADATA_ENTRY: &str = "__METADATA__";
+
+/// the most memory that is reserved upfront for a zip entry, its declared size may be forged
+const MAX_PREALLOCATION: u64 = 1 << 20;
+
+/// The format of the archive the tika server packs the embedded resources into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    /// a zip archive, the default of the tika server
+    #[default]
+    Zip,
+    /// a tar archive
+    Tar,
+}
+
+impl ArchiveFormat {
+    /// the value of the `Accept` header to request this format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::Tar => "application/x-tar",
+        }
+    }
+}
+
+/// The embedded resources of a document, returned by the `/unpack` endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnpackedResources {
+    /// the name and content of each resource, in the order of the archive
+    pub entries: Vec<(String, Vec<u8>)>,
+}
+
+impl UnpackedResources {
+    /// Reads all file entries of the `archive`
+    pub fn from_archive(format: ArchiveFormat, archive: Vec<u8>) -> Result<Self> {
+        if archive.is_empty() {
+            // the tika server responds with `204 No Content` if there is nothing to unpack
+            return Ok(Self::default());
+        }
+        match format {
+            ArchiveFormat::Zip => Self::from_zip(archive),
+            ArchiveFormat::Tar => Self::from_tar(archive),
+        }
+    }
+
+    fn from_zip(archive: Vec<u8>) -> Result<Self> {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive))?;
+        let mut entries = Vec::with_capacity(zip.len());
+        for idx in 0..zip.len() {
+            let mut file = zip.by_index(idx)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut content = Vec::with_capacity(file.size().min(MAX_PREALLOCATION) as usize);
+            file.read_to_end(&mut content)?;
+            entries.push((file.name().to_string(), content));
+        }
+        Ok(UnpackedResources { entries })
+    }
+
+    fn from_tar(archive: Vec<u8>) -> Result<Self> {
+        let mut tar = tar::Archive::new(Cursor::new(archive));
+        let mut entries = Vec::new();
+        for file in tar.entries()? {
+            let mut file = file?;
+            if !file.header().entry_type().is_file() {
+                continue;
+            }
+            let name = String::from_utf8_lossy(&file.path_bytes()).into_owned();
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            entries.push((name, content));
+        }
+        Ok(UnpackedResources { entries })
+    }
+
+    /// iterates over the name and content of all resources
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.entries
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_slice()))
+    }
+
+    /// the content of the resource with the `name`
+    pub fn get<T: AsRef<str>>(&self, name: T) -> Option<&[u8]> {
+        self.iter()
+            .find(|(n, _)| *n == name.as_ref())
+            .map(|(_, content)| content)
+    }
+
+    /// removes the resource with the `name` and returns its content
+    pub fn remove<T: AsRef<str>>(&mut self, name: T) -> Option<Vec<u8>> {
+        let idx = self.entries.iter().position(|(n, _)| n == name.as_ref())?;
+        Some(self.entries.remove(idx).1)
+    }
+
+    /// the number of resources
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// whether there are no resources
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes all resources into the `dir`, which will be created if it does not exist.
+    /// The names of the resources are sanitized with `sanitize_name`, so that no resource is
+    /// written outside of the `dir`.
+    ///
+    /// Every resource is written to its own file. If its sanitized name collides with a
+    /// resource written before, like `a/b` and `a\b`, or a file `a` and a resource `a/b`, the
+    /// colliding part of the name gets a numeric suffix, like `b-1`, and the resource is
+    /// reported as `renamed`. Names that only differ in case collide as well, as they do on
+    /// case insensitive file systems.
+    ///
+    /// Existing files are overwritten, but symlinks within the `dir` are never followed:
+    /// writing a resource to a symlink or into a symlinked directory fails.
+    /// Returns all written resources in the order of the archive.
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<WrittenResource>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut claims = PathClaims::default();
+        let mut written = Vec::with_capacity(self.entries.len());
+        for (idx, (name, content)) in self.entries.iter().enumerate() {
+            let sanitized =
+                sanitize_name(name).unwrap_or_else(|| PathBuf::from(format!("resource-{}", idx)));
+            let relative = claims.claim(&sanitized);
+            let renamed = relative != sanitized;
+            let path = dir.join(&relative);
+            if renamed {
+                warn!(
+                    "Unpacked {} to {}, its name collides with another resource",
+                    name,
+                    path.display()
+                );
+            }
+            create_parents(dir, &relative)?;
+            write_new(&path, content)?;
+            debug!("Unpacked {} to {}", name, path.display());
+            written.push(WrittenResource {
+                name: name.clone(),
+                path,
+                renamed,
+            });
+        }
+        Ok(written)
+    }
+}
+
+/// Creates the parent directories of the `relative` path within the `dir`, without following
+/// symlinks.
+fn create_parents(dir: &Path, relative: &Path) -> io::Result<()> {
+    let mut current = dir.to_path_buf();
+    for part in relative.parent().into_iter().flat_map(Path::iter) {
+        current.push(part);
+        match fs::symlink_metadata(&current) {
+            Ok(meta) if meta.is_dir() => {}
+            Ok(_) => return Err(not_writable(&current)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => fs::create_dir(&current)?,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `content` to a new file at `path`, an existing regular file is replaced.
+/// The file is created exclusively, so that a symlink at the `path` is never followed.
+fn write_new(path: &Path, content: &[u8]) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_file() => fs::remove_file(path)?,
+        Ok(_) => return Err(not_writable(path)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(content)
+}
+
+fn not_writable(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!(
+            "Can't unpack to {}, it is a symlink or not a regular file",
+            path.display()
+        ),
+    )
+}
+
+/// A resource that `UnpackedResources::write_to` wrote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenResource {
+    /// the name of the resource in the archive
+    pub name: String,
+    /// the path of the written file
+    pub path: PathBuf,
+    /// whether the resource was written under another name than its sanitized name, because
+    /// that collided with another resource
+    pub renamed: bool,
+}
+
+/// The relative paths of the files and directories `write_to` already used
+#[derive(Debug, Default)]
+struct PathClaims {
+    files: HashSet<String>,
+    dirs: HashSet<String>,
+}
+
+impl PathClaims {
+    /// Claims a path for the file at the sanitized `path`.
+    /// Directories that collide with a claimed file and a file name that collides with a
+    /// claimed file or directory get a numeric suffix.
+    fn claim(&mut self, path: &Path) -> PathBuf {
+        let parts: Vec<_> = path.iter().map(|part| part.to_string_lossy()).collect();
+        let (file, dirs) = parts.split_last().expect("sanitized names are not empty");
+        let mut claimed = PathBuf::new();
+        for dir in dirs {
+            let dir = self.free(&claimed, dir, |claims, key| !claims.files.contains(key));
+            claimed.push(dir);
+            self.dirs.insert(claim_key(&claimed));
+        }
+        let file = self.free(&claimed, file, |claims, key| {
+            !claims.files.contains(key) && !claims.dirs.contains(key)
+        });
+        claimed.push(file);
+        self.files.insert(claim_key(&claimed));
+        claimed
+    }
+
+    /// the `part`, or the `part` with the first numeric suffix, that is free within `parent`
+    fn free<F>(&self, parent: &Path, part: &str, is_free: F) -> String
+    where
+        F: Fn(&Self, &str) -> bool,
+    {
+        (0..)
+            .map(|n| {
+                if n == 0 {
+                    part.to_string()
+                } else {
+                    with_numeric_suffix(part, n)
+                }
+            })
+            .find(|candidate| is_free(self, &claim_key(&parent.join(candidate))))
+            .expect("there are infinitely many suffixes")
+    }
+}
+
+/// paths that only differ in case are the same on case insensitive file systems
+fn claim_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// `image.png` with the suffix `n` is `image-n.png`
+fn with_numeric_suffix(name: &str, n: usize) -> String {
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}-{}{}", &name[..dot], n, &name[dot..]),
+        _ => format!("{}-{}", name, n),
+    }
+}
+
+impl IntoIterator for UnpackedResources {
+    type Item = (String, Vec<u8>);
+    type IntoIter = vec::IntoIter<(String, Vec<u8>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UnpackedResources {
+    type Item = &'a (String, Vec<u8>);
+    type IntoIter = slice::Iter<'a, (String, Vec<u8>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+/// The embedded resources of a document together with the text and metadata of the container
+/// document, returned by the `/unpack/all` endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnpackedAll {
+    /// the embedded resources, without the `__TEXT__` and `__METADATA__` entries
+    pub resources: UnpackedResources,
+    /// the text of the container document
+    pub text: Option<String>,
+    /// the metadata of the container document
+    pub metadata: Option<Metadata>,
+}
+
+impl From<UnpackedResources> for UnpackedAll {
+    fn from(mut resources: UnpackedResources) -> Self {
+        let text = resources
+            .remove(TEXT_ENTRY)
+            .map(|text| String::from_utf8_lossy(&text).into_owned());
+        let metadata = resources
+            .remove(METADATA_ENTRY)
+            .map(|meta| parse_metadata_csv(&String::from_utf8_lossy(&meta)));
+        UnpackedAll {
+            resources,
+            text,
+            metadata,
+        }
+    }
+}
+
+/// Converts the name of a resource into a relative path that can't escape the directory it is
+/// joined to.
+/// Separators are unified, windows prefixes like `C:` or `\\?\`, roots, `.` and `..`
+/// components are dropped and characters that are invalid in file names on common platforms
+/// are replaced with `_`. Names that windows reserves for devices, like `CON` or `nul.txt`,
+/// get a `_` appended to their stem, like `CON_` or `nul_.txt`.
+/// The result is the same on all platforms.
+/// Returns `None` if nothing of the name remains.
+pub fn sanitize_name<T: AsRef<str>>(name: T) -> Option<PathBuf> {
+    let name = name.as_ref().replace('\\', "/");
+    let mut parts = name.split('/').filter(|part| !part.is_empty()).peekable();
+    // the verbatim and device prefixes `\\?\` and `\\.\`
+    if name.starts_with("//") && matches!(parts.peek(), Some(&"?") | Some(&".")) {
+        parts.next();
+    }
+    let mut path = PathBuf::new();
+    for (idx, part) in parts.enumerate() {
+        let part = match part.as_bytes() {
+            // a drive, like `C:` or the drive relative `C:file`
+            [drive, b':', ..] if idx == 0 && drive.is_ascii_alphabetic() => &part[2..],
+            _ => part,
+        };
+        if part == "." || part == ".." {
+            continue;
+        }
+        let part: String = part
+            .chars()
+            .map(|c| match c {
+                '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+        let part = part.trim_end_matches(&['.', ' '][..]);
+        if is_reserved_name(part) {
+            path.push(match part.find('.') {
+                Some(dot) => format!("{}_{}", &part[..dot], &part[dot..]),
+                None => format!("{}_", part),
+            });
+        } else if !part.is_empty() {
+            path.push(part);
+        }
+    }
+    if path.as_os_str().is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Whether windows reserves the file `name` for a device, regardless of its extension,
+/// like `CON`, `nul.txt` or `com1.tar.gz`
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    let stem = stem.trim_end_matches(' ').to_ascii_uppercase();
+    match stem.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" | "CONIN$" | "CONOUT$" => true,
+        _ => {
+            let mut chars = stem.chars();
+            let device: String = chars.by_ref().take(3).collect();
+            let port = chars.next();
+            (device == "COM" || device == "LPT")
+                && matches!(port, Some('0'..='9') | Some('¹') | Some('²') | Some('³'))
+                && chars.next().is_none()
+        }
+    }
+}
+
+/// Parses the `__METADATA__` entry, which contains a csv record per metadata key with the key
+/// as first field followed by all its values.
+fn parse_metadata_csv(csv: &str) -> Metadata {
+    let mut metadata = HashMap::new();
+    for mut record in parse_csv(csv) {
+        if record.iter().all(String::is_empty) {
+            continue;
+        }
+        let key = record.remove(0);
+        let value = if record.len() == 1 {
+            MetadataValue::Single(record.remove(0))
+        } else {
+            MetadataValue::Multi(record)
+        };
+        metadata.insert(key, value);
+    }
+    Metadata(metadata)
+}
+
+/// A minimal RFC 4180 csv reader, that supports quoted fields with escaped quotes and line breaks
+fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn sanitized(name: &str) -> Option<String> {
+        sanitize_name(name).map(|path| {
+            path.iter()
+                .map(|part| part.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+    }
+
+    fn resources(names: &[&str]) -> UnpackedResources {
+        UnpackedResources {
+            entries: names
+                .iter()
+                .map(|name| (name.to_string(), name.as_bytes().to_vec()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn sanitizes_parent_components() {
+        assert_eq!(
+            sanitized("../../etc/passwd"),
+            Some("etc/passwd".to_string())
+        );
+        assert_eq!(sanitized("a/../b/./c.png"), Some("a/b/c.png".to_string()));
+        assert_eq!(sanitized(".."), None);
+        assert_eq!(sanitized("../."), None);
+    }
+
+    #[test]
+    fn sanitizes_absolute_paths() {
+        assert_eq!(sanitized("/etc/passwd"), Some("etc/passwd".to_string()));
+        assert_eq!(
+            sanitized("//server/share/a.txt"),
+            Some("server/share/a.txt".to_string())
+        );
+        assert_eq!(sanitized("/"), None);
+    }
+
+    #[test]
+    fn sanitizes_windows_prefixes() {
+        assert_eq!(
+            sanitized("C:\\Windows\\win.ini"),
+            Some("Windows/win.ini".to_string())
+        );
+        assert_eq!(sanitized("c:file.txt"), Some("file.txt".to_string()));
+        assert_eq!(
+            sanitized("\\\\?\\C:\\a\\b.txt"),
+            Some("a/b.txt".to_string())
+        );
+        assert_eq!(sanitized("\\\\.\\pipe\\x"), Some("pipe/x".to_string()));
+        assert_eq!(sanitized("..\\..\\a.txt"), Some("a.txt".to_string()));
+        assert_eq!(sanitized("C:"), None);
+    }
+
+    #[test]
+    fn sanitizes_invalid_characters() {
+        assert_eq!(sanitized("a<b>:c?.png"), Some("a_b__c_.png".to_string()));
+        assert_eq!(sanitized("dir. /file. "), Some("dir/file".to_string()));
+        assert_eq!(sanitized("a\u{0}b"), Some("a_b".to_string()));
+    }
+
+    #[test]
+    fn sanitizes_reserved_names() {
+        assert_eq!(sanitized("CON"), Some("CON_".to_string()));
+        assert_eq!(sanitized("a/nul.txt"), Some("a/nul_.txt".to_string()));
+        assert_eq!(sanitized("Com1.tar.gz"), Some("Com1_.tar.gz".to_string()));
+        assert_eq!(sanitized("lpt9/x"), Some("lpt9_/x".to_string()));
+        assert_eq!(sanitized("aux ."), Some("aux_".to_string()));
+        assert_eq!(sanitized("console.txt"), Some("console.txt".to_string()));
+        assert_eq!(sanitized("com10"), Some("com10".to_string()));
+    }
+
+    #[test]
+    fn numeric_suffixes() {
+        assert_eq!(with_numeric_suffix("image.png", 1), "image-1.png");
+        assert_eq!(with_numeric_suffix("archive.tar.gz", 2), "archive.tar-2.gz");
+        assert_eq!(with_numeric_suffix("a", 1), "a-1");
+        assert_eq!(with_numeric_suffix(".hidden", 1), ".hidden-1");
+    }
+
+    #[test]
+    fn writes_colliding_names_to_their_own_files() {
+        let dir = scratch_dir("unpack-collisions");
+        let written = resources(&["a/b.png", "a\\b.png", "../a/b.png", "A/B.png", "..", "x"])
+            .write_to(&dir)
+            .unwrap();
+        let paths: Vec<_> = written
+            .iter()
+            .map(|res| res.path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("a/b.png"),
+                PathBuf::from("a/b-1.png"),
+                PathBuf::from("a/b-2.png"),
+                PathBuf::from("A/B-3.png"),
+                PathBuf::from("resource-4"),
+                PathBuf::from("x"),
+            ]
+        );
+        let renamed: Vec<_> = written.iter().map(|res| res.renamed).collect();
+        assert_eq!(renamed, vec![false, true, true, true, false, false]);
+        for res in &written {
+            assert_eq!(fs::read(&res.path).unwrap(), res.name.as_bytes());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_files_and_directories_of_the_same_name() {
+        let dir = scratch_dir("unpack-file-and-dir");
+        let written = resources(&["a", "a/b", "a/c", "d/e", "d"])
+            .write_to(&dir)
+            .unwrap();
+        let paths: Vec<_> = written
+            .iter()
+            .map(|res| res.path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("a"),
+                PathBuf::from("a-1/b"),
+                PathBuf::from("a-1/c"),
+                PathBuf::from("d/e"),
+                PathBuf::from("d-1"),
+            ]
+        );
+        for res in &written {
+            assert_eq!(fs::read(&res.path).unwrap(), res.name.as_bytes());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replaces_existing_files() {
+        let dir = scratch_dir("unpack-existing");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a"), "old").unwrap();
+        resources(&["a"]).write_to(&dir).unwrap();
+        assert_eq!(fs::read(dir.join("a")).unwrap(), b"a");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = scratch_dir("unpack-symlinks");
+        let outside = scratch_dir("unpack-symlinks-outside");
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("target"), "outside").unwrap();
+        symlink(outside.join("target"), dir.join("file")).unwrap();
+        symlink(&outside, dir.join("dir")).unwrap();
+
+        assert!(resources(&["file"]).write_to(&dir).is_err());
+        assert!(resources(&["dir/target"]).write_to(&dir).is_err());
+        assert!(resources(&["dir/new"]).write_to(&dir).is_err());
+        assert_eq!(fs::read(outside.join("target")).unwrap(), b"outside");
+        assert!(!outside.join("new").exists());
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn parses_plain_csv() {
+        assert_eq!(
+            parse_csv("a,b,c\r\nd,,f\n"),
+            vec![vec!["a", "b", "c"], vec!["d", "", "f"]]
+        );
+        assert_eq!(parse_csv("a,b"), vec![vec!["a", "b"]]);
+        assert_eq!(parse_csv("a,\n"), vec![vec!["a", ""]]);
+        assert!(parse_csv("").is_empty());
+    }
+
+    #[test]
+    fn parses_quoted_csv_fields() {
+        assert_eq!(
+            parse_csv("\"a,b\",\"say \"\"hi\"\"\",\"\"\n"),
+            vec![vec!["a,b", "say \"hi\"", ""]]
+        );
+        assert_eq!(
+            parse_csv("\"line\r\nbreak\",x\nnext\n"),
+            vec![vec!["line\r\nbreak", "x"], vec!["next"]]
+        );
+        assert_eq!(parse_csv("\"\"\"\""), vec![vec!["\""]]);
+    }
+
+    #[test]
+    fn parses_metadata_csv() {
+        let metadata = parse_metadata_csv("\"dc:title\",\"A, B\"\n\nauthors,\"Doe, J\",Roe\n");
+        assert_eq!(
+            metadata.0.get("dc:title"),
+            Some(&MetadataValue::Single("A, B".to_string()))
+        );
+        assert_eq!(
+            metadata.0.get("authors"),
+            Some(&MetadataValue::Multi(vec![
+                "Doe, J".to_string(),
+                "Roe".to_string()
+            ]))
+        );
+        assert_eq!(metadata.0.len(), 2);
+    }
+}